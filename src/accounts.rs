@@ -0,0 +1,95 @@
+use log::debug;
+use serde::Deserialize;
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+#[derive(Debug)]
+pub enum AccountsError {
+    IOError(io::Error),
+    ParseError(String),
+}
+
+impl fmt::Display for AccountsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AccountsError::IOError(err) => write!(f, "Cannot read accounts file: {}", err),
+            AccountsError::ParseError(msg) => write!(f, "Invalid accounts file: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for AccountsError {}
+
+impl From<io::Error> for AccountsError {
+    fn from(err: io::Error) -> Self {
+        AccountsError::IOError(err)
+    }
+}
+
+/// Account reached by assuming a role, optionally through intermediate roles
+#[derive(Debug, Clone, Deserialize)]
+pub struct AccountTarget {
+    /// Human friendly name used in reports, defaults to account ID
+    pub name: Option<String>,
+    /// Role assumed in the target account
+    pub role_arn: String,
+    /// Roles assumed in order before `role_arn`
+    #[serde(default)]
+    pub via: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AccountsFile {
+    #[serde(default)]
+    accounts: Vec<AccountTarget>,
+}
+
+impl AccountTarget {
+    pub fn new(role_arn: String) -> Self {
+        AccountTarget {
+            name: None,
+            role_arn,
+            via: Vec::new(),
+        }
+    }
+
+    /// Every role that has to be assumed to reach the account, in order
+    pub fn chain(&self) -> impl Iterator<Item = &String> {
+        self.via.iter().chain(std::iter::once(&self.role_arn))
+    }
+
+    /// ID of the target account, taken from role ARN
+    pub fn account(&self) -> String {
+        account_id(&self.role_arn).unwrap_or(self.role_arn.clone())
+    }
+
+    /// Short name of the assumed role, taken from role ARN
+    pub fn role(&self) -> String {
+        self.role_arn.rsplit('/').next().unwrap_or(&self.role_arn).to_string()
+    }
+
+    /// Name displayed in reports
+    pub fn label(&self) -> String {
+        self.name.clone().unwrap_or(self.account())
+    }
+}
+
+/// Extract account ID from ARN (`arn:aws:iam::<account>:role/<name>`)
+pub fn account_id(arn: &str) -> Option<String> {
+    arn.split(':')
+        .nth(4)
+        .filter(|id| !id.is_empty())
+        .map(|id| id.to_string())
+}
+
+/// Load account targets declared in TOML file as `[[accounts]]` tables
+pub fn load_accounts(path: &Path) -> Result<Vec<AccountTarget>, AccountsError> {
+    debug!("Loading accounts from {}", path.display());
+    let content = fs::read_to_string(path)?;
+    let parsed: AccountsFile = toml::from_str(&content)
+        .map_err(|e| AccountsError::ParseError(e.to_string()))?;
+    Ok(parsed.accounts)
+}
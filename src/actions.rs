@@ -1,28 +1,973 @@
-// NOTE: will be refactored to use this trait later
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
-#[allow(dead_code)]
+use crate::outputs::Renderable;
+
+#[derive(Debug)]
 /// Define possible errors that can occur in provider actions
 pub enum ProviderError {
     ConfigurationError,
     ConnectionError,
     AuthenticationError,
-    ResourceNotFound,
+    ResourceNotFound(String),
     TimeoutError,
     PermissionError,
     GeneralError(String),
+    /// Session of the given profile has expired and has to be renewed
+    SsoTokenExpired(Option<String>),
+    /// No credentials could be found for the given profile
+    MissingCredentials(Option<String>),
+    /// Caller is not allowed to perform an action
+    AccessDenied {
+        action: Option<String>,
+        request_id: Option<String>,
+    },
+    /// Request was rejected due to rate limiting
+    Throttled {
+        request_id: Option<String>,
+    },
+    /// Endpoint of the given region cannot be resolved
+    UnknownRegion(String),
+    /// Endpoint could not be reached
+    EndpointError(String),
+    /// Target matches more than a single resource
+    AmbiguousTarget {
+        target: String,
+        matches: Vec<String>,
+    },
+    /// Any other error reported by the service
+    ServiceError {
+        code: String,
+        message: String,
+        request_id: Option<String>,
+    },
+}
+
+impl std::fmt::Display for ProviderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProviderError::ConfigurationError => write!(f, "Configuration error"),
+            ProviderError::ConnectionError => write!(f, "Connection error"),
+            ProviderError::AuthenticationError => write!(f, "Authentication failed"),
+            ProviderError::ResourceNotFound(msg) => write!(f, "{}", msg),
+            ProviderError::TimeoutError => write!(f, "Operation timed out"),
+            ProviderError::PermissionError => write!(f, "Permission denied"),
+            ProviderError::GeneralError(msg) => write!(f, "{}", msg),
+            ProviderError::SsoTokenExpired(profile) => write!(f, "Session of profile {} has expired", profile.as_deref().unwrap_or("default")),
+            ProviderError::MissingCredentials(profile) => write!(f, "No credentials found for profile {}", profile.as_deref().unwrap_or("default")),
+            ProviderError::AccessDenied {action, request_id} => {
+                write!(f, "Access denied to {}", action.as_deref().unwrap_or("requested action"))?;
+                write_request_id(f, request_id)
+            }
+            ProviderError::Throttled {request_id} => {
+                write!(f, "Request throttled")?;
+                write_request_id(f, request_id)
+            }
+            ProviderError::UnknownRegion(region) => write!(f, "Unknown region {}", region),
+            ProviderError::EndpointError(msg) => write!(f, "Endpoint unreachable: {}", msg),
+            ProviderError::AmbiguousTarget {target, matches} => write!(f, "'{}' matches several resources: {}", target, matches.join(", ")),
+            ProviderError::ServiceError {code, message, request_id} => {
+                write!(f, "{}: {}", code, message)?;
+                write_request_id(f, request_id)
+            }
+        }
+    }
+}
+
+fn write_request_id(f: &mut std::fmt::Formatter<'_>, request_id: &Option<String>) -> std::fmt::Result {
+    match request_id {
+        Some(id) => write!(f, " (request ID: {})", id),
+        None => Ok(()),
+    }
+}
+
+/// Failure of a single target (e.g. region or account) during fan-out, reported alongside partial results
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PartialFailure {
+    pub target: String,
+    pub error: String,
+}
+
+/// Define collections that can be assembled from results of several targets
+pub trait Mergeable: Default {
+    /// Append items of another collection
+    fn merge(&mut self, other: Self);
+    /// Record target which could not be queried
+    fn add_failure(&mut self, failure: PartialFailure);
+    /// Mark every item and failure as coming from given account and role
+    fn set_account(&mut self, account: &str, role: &str);
+}
+
+/// Identity of the caller as seen by the cloud provider
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Identity {
+    pub account: String,
+    pub arn: String,
+    pub user_id: String,
+    /// Credentials profile used to authenticate, if applicable
+    #[serde(default)]
+    pub profile: Option<String>,
+    /// Region requests are sent to, if applicable
+    #[serde(default)]
+    pub region: Option<String>,
+}
+
+impl Renderable for Identity {
+    type Record = Identity;
+
+    fn records(&self) -> &[Identity] {
+        std::slice::from_ref(self)
+    }
+}
+
+/// Single compute instance
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Instance {
+    pub name: String,
+    pub instance_id: String,
+    pub state: String,
+    pub private_ip: String,
+    /// Region the instance is running in
+    #[serde(default)]
+    pub region: Option<String>,
+    /// Account the instance belongs to, when listing several accounts
+    #[serde(default)]
+    pub account: Option<String>,
+    /// Role assumed to reach the account
+    #[serde(default)]
+    pub role: Option<String>,
+    #[serde(default)]
+    pub instance_type: Option<String>,
+    #[serde(default)]
+    pub availability_zone: Option<String>,
+    #[serde(default)]
+    pub public_ip: Option<String>,
+    /// Launch time in RFC 3339 format
+    #[serde(default)]
+    pub launch_time: Option<String>,
+    #[serde(default)]
+    pub image_id: Option<String>,
+    #[serde(default)]
+    pub key_name: Option<String>,
+    #[serde(default)]
+    pub vpc_id: Option<String>,
+    #[serde(default)]
+    pub subnet_id: Option<String>,
+    #[serde(default)]
+    pub iam_profile: Option<String>,
+    #[serde(default)]
+    pub platform: Option<String>,
+    #[serde(default)]
+    pub tags: BTreeMap<String, String>,
+}
+
+/// Column of instance listing, selectable with `--columns`
+#[derive(Debug, Clone, PartialEq)]
+pub enum InstanceColumn {
+    Name,
+    Id,
+    State,
+    PrivateIp,
+    Region,
+    Account,
+    Role,
+    Type,
+    Az,
+    PublicIp,
+    LaunchTime,
+    Ami,
+    KeyPair,
+    Vpc,
+    Subnet,
+    IamProfile,
+    Platform,
+    /// Every tag as `key=value` list
+    Tags,
+    /// Value of a single tag
+    Tag(String),
+}
+
+impl InstanceColumn {
+    /// Columns shown when none were selected
+    pub fn defaults() -> Vec<InstanceColumn> {
+        vec![
+            InstanceColumn::Name,
+            InstanceColumn::Id,
+            InstanceColumn::State,
+            InstanceColumn::PrivateIp,
+            InstanceColumn::Region,
+            InstanceColumn::Account,
+            InstanceColumn::Role,
+        ]
+    }
+
+    /// Header displayed above the column
+    pub fn header(&self) -> String {
+        match self {
+            InstanceColumn::Name => "Name".to_string(),
+            InstanceColumn::Id => "Instance ID".to_string(),
+            InstanceColumn::State => "State".to_string(),
+            InstanceColumn::PrivateIp => "Private IP".to_string(),
+            InstanceColumn::Region => "Region".to_string(),
+            InstanceColumn::Account => "Account".to_string(),
+            InstanceColumn::Role => "Role".to_string(),
+            InstanceColumn::Type => "Type".to_string(),
+            InstanceColumn::Az => "AZ".to_string(),
+            InstanceColumn::PublicIp => "Public IP".to_string(),
+            InstanceColumn::LaunchTime => "Launch Time".to_string(),
+            InstanceColumn::Ami => "AMI".to_string(),
+            InstanceColumn::KeyPair => "Key Pair".to_string(),
+            InstanceColumn::Vpc => "VPC".to_string(),
+            InstanceColumn::Subnet => "Subnet".to_string(),
+            InstanceColumn::IamProfile => "IAM Profile".to_string(),
+            InstanceColumn::Platform => "Platform".to_string(),
+            InstanceColumn::Tags => "Tags".to_string(),
+            InstanceColumn::Tag(key) => format!("tag:{}", key),
+        }
+    }
+
+    /// Value of the column for given instance, `-` when missing
+    pub fn value(&self, instance: &Instance) -> String {
+        let value = match self {
+            InstanceColumn::Name => Some(instance.name.clone()),
+            InstanceColumn::Id => Some(instance.instance_id.clone()),
+            InstanceColumn::State => Some(instance.state.clone()),
+            InstanceColumn::PrivateIp => Some(instance.private_ip.clone()),
+            InstanceColumn::Region => instance.region.clone(),
+            InstanceColumn::Account => instance.account.clone(),
+            InstanceColumn::Role => instance.role.clone(),
+            InstanceColumn::Type => instance.instance_type.clone(),
+            InstanceColumn::Az => instance.availability_zone.clone(),
+            InstanceColumn::PublicIp => instance.public_ip.clone(),
+            InstanceColumn::LaunchTime => instance.launch_time.clone(),
+            InstanceColumn::Ami => instance.image_id.clone(),
+            InstanceColumn::KeyPair => instance.key_name.clone(),
+            InstanceColumn::Vpc => instance.vpc_id.clone(),
+            InstanceColumn::Subnet => instance.subnet_id.clone(),
+            InstanceColumn::IamProfile => instance.iam_profile.clone(),
+            InstanceColumn::Platform => instance.platform.clone(),
+            InstanceColumn::Tags => Some(instance.tags.iter()
+                .map(|(key, value)| format!("{}={}", key, value))
+                .collect::<Vec<String>>()
+                .join(",")),
+            InstanceColumn::Tag(key) => instance.tags.get(key).cloned(),
+        };
+        value.filter(|v| !v.is_empty()).unwrap_or("-".to_string())
+    }
+}
+
+impl std::str::FromStr for InstanceColumn {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(key) = s.strip_prefix("tag:") {
+            return Ok(InstanceColumn::Tag(key.to_string()));
+        }
+
+        match s.to_lowercase().replace('_', "-").as_str() {
+            "name" => Ok(InstanceColumn::Name),
+            "id" => Ok(InstanceColumn::Id),
+            "state" => Ok(InstanceColumn::State),
+            "private-ip" => Ok(InstanceColumn::PrivateIp),
+            "region" => Ok(InstanceColumn::Region),
+            "account" => Ok(InstanceColumn::Account),
+            "role" => Ok(InstanceColumn::Role),
+            "type" => Ok(InstanceColumn::Type),
+            "az" => Ok(InstanceColumn::Az),
+            "public-ip" => Ok(InstanceColumn::PublicIp),
+            "launch-time" => Ok(InstanceColumn::LaunchTime),
+            "ami" => Ok(InstanceColumn::Ami),
+            "key-pair" => Ok(InstanceColumn::KeyPair),
+            "vpc" => Ok(InstanceColumn::Vpc),
+            "subnet" => Ok(InstanceColumn::Subnet),
+            "iam-profile" => Ok(InstanceColumn::IamProfile),
+            "platform" => Ok(InstanceColumn::Platform),
+            "tags" => Ok(InstanceColumn::Tags),
+            _ => Err(format!(
+                "unknown column '{}', expected one of: name, id, state, private-ip, region, account, role, type, az, \
+                public-ip, launch-time, ami, key-pair, vpc, subnet, iam-profile, platform, tags, tag:<key>", s
+            )),
+        }
+    }
+}
+
+/// Network interface attached to an instance
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NetworkInterface {
+    pub interface_id: String,
+    pub private_ip: Option<String>,
+    pub public_ip: Option<String>,
+    pub subnet_id: Option<String>,
+    pub security_groups: Vec<String>,
+}
+
+/// Security group protecting an instance
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SecurityGroup {
+    pub group_id: String,
+    pub group_name: Option<String>,
+}
+
+/// Block device attached to an instance
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BlockDevice {
+    pub device_name: String,
+    pub volume_id: Option<String>,
+    pub status: Option<String>,
+    pub delete_on_termination: Option<bool>,
+}
+
+/// Detailed description of a single instance
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InstanceDetails {
+    pub instance: Instance,
+    #[serde(default)]
+    pub network_interfaces: Vec<NetworkInterface>,
+    #[serde(default)]
+    pub security_groups: Vec<SecurityGroup>,
+    #[serde(default)]
+    pub block_devices: Vec<BlockDevice>,
+    /// State of detailed monitoring
+    #[serde(default)]
+    pub monitoring: Option<String>,
+    /// Result of system status check
+    #[serde(default)]
+    pub system_status: Option<String>,
+    /// Result of instance status check
+    #[serde(default)]
+    pub instance_status: Option<String>,
+}
+
+impl Renderable for InstanceDetails {
+    type Record = InstanceDetails;
+
+    fn records(&self) -> &[InstanceDetails] {
+        std::slice::from_ref(self)
+    }
+}
+
+/// Collection of compute instances
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InstanceList {
+    pub instances: Vec<Instance>,
+    /// Columns rendered in table output, defaults when empty
+    #[serde(skip)]
+    pub columns: Vec<InstanceColumn>,
+    /// Targets which failed to return instances
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub failures: Vec<PartialFailure>,
+}
+
+impl InstanceList {
+    pub fn new() -> Self {
+        InstanceList {
+            instances: Vec::new(),
+            columns: Vec::new(),
+            failures: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, instance: Instance) {
+        self.instances.push(instance);
+    }
+}
+
+impl FromIterator<Instance> for InstanceList {
+    fn from_iter<I: IntoIterator<Item = Instance>>(iter: I) -> Self {
+        let instances: Vec<Instance> = iter.into_iter().collect();
+        InstanceList { instances, columns: Vec::new(), failures: Vec::new() }
+    }
+}
+
+impl Default for InstanceList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Mergeable for InstanceList {
+    fn merge(&mut self, other: Self) {
+        self.instances.extend(other.instances);
+        self.failures.extend(other.failures);
+    }
+
+    fn add_failure(&mut self, failure: PartialFailure) {
+        self.failures.push(failure);
+    }
+
+    fn set_account(&mut self, account: &str, role: &str) {
+        for instance in self.instances.iter_mut() {
+            instance.account = Some(account.to_string());
+            instance.role = Some(role.to_string());
+        }
+        for failure in self.failures.iter_mut() {
+            failure.target = format!("{}/{}", account, failure.target);
+        }
+    }
+}
+
+impl Renderable for InstanceList {
+    type Record = Instance;
+
+    fn records(&self) -> &[Instance] {
+        &self.instances
+    }
+}
+
+/// Lifecycle action performed on instances
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InstanceAction {
+    Start,
+    Stop,
+    Reboot,
+    Terminate,
+}
+
+impl InstanceAction {
+    /// State instances end up in once the action completes
+    pub fn target_state(&self) -> &'static str {
+        match self {
+            InstanceAction::Start | InstanceAction::Reboot => "running",
+            InstanceAction::Stop => "stopped",
+            InstanceAction::Terminate => "terminated",
+        }
+    }
+}
+
+impl std::fmt::Display for InstanceAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InstanceAction::Start => write!(f, "start"),
+            InstanceAction::Stop => write!(f, "stop"),
+            InstanceAction::Reboot => write!(f, "reboot"),
+            InstanceAction::Terminate => write!(f, "terminate"),
+        }
+    }
+}
+
+/// State transition of a single instance caused by lifecycle action
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StateChange {
+    pub instance_id: String,
+    pub previous_state: Option<String>,
+    pub current_state: String,
+}
+
+/// Result of lifecycle action
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StateChangeList {
+    pub changes: Vec<StateChange>,
+}
+
+impl Renderable for StateChangeList {
+    type Record = StateChange;
+
+    fn records(&self) -> &[StateChange] {
+        &self.changes
+    }
+}
+
+/// Options narrowing down instance listing
+#[derive(Debug, Default, Clone)]
+pub struct InstanceQuery {
+    /// Maximum number of instances to return
+    pub max_items: Option<usize>,
+    /// Number of instances requested per API call
+    pub page_size: Option<i32>,
+    /// Raw provider-specific filters, as name and value
+    pub filters: Vec<(String, String)>,
+    /// Tags that instances must carry, as key and value
+    pub tags: Vec<(String, String)>,
+    /// Accepted instance states
+    pub states: Vec<String>,
+    /// Glob pattern (`*`, `?`) matched against instance name
+    pub name: Option<String>,
+    /// Network the instances must belong to
+    pub vpc: Option<String>,
+    /// Subnet the instances must belong to
+    pub subnet: Option<String>,
+    /// Exact instance IDs to return
+    pub instance_ids: Vec<String>,
+}
+
+impl InstanceQuery {
+    /// Check whether query narrows down the listing in any way
+    pub fn has_filters(&self) -> bool {
+        !self.filters.is_empty()
+            || !self.tags.is_empty()
+            || !self.states.is_empty()
+            || self.name.is_some()
+            || self.vpc.is_some()
+            || self.subnet.is_some()
+            || !self.instance_ids.is_empty()
+    }
+
+    /// Check instance name against the name pattern, if any
+    pub fn matches_name(&self, name: &str) -> bool {
+        match &self.name {
+            Some(pattern) => glob_match(pattern, name),
+            None => true,
+        }
+    }
+}
+
+/// Match text against a glob pattern supporting `*` (any sequence) and `?` (any character)
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+
+    let (mut p, mut t) = (0, 0);
+    // position of the last `*` in pattern and text position it was matched at
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if let Some((star, matched)) = backtrack {
+            // let the last `*` consume one more character
+            p = star + 1;
+            t = matched + 1;
+            backtrack = Some((star, matched + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// Single configuration parameter
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Parameter {
+    pub name: String,
+    pub r#type: String,
+    pub value: String,
+    /// Region the parameter is stored in
+    #[serde(default)]
+    pub region: Option<String>,
+    /// Account the parameter belongs to, when listing several accounts
+    #[serde(default)]
+    pub account: Option<String>,
+    /// Role assumed to reach the account
+    #[serde(default)]
+    pub role: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_modified: Option<String>,
+    /// `text` or `aws:ec2:image`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data_type: Option<String>,
+    /// User who made the last change, this and the fields below are filled only when metadata is requested
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modified_by: Option<String>,
+    /// `Standard`, `Advanced` or `Intelligent-Tiering`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tier: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kms_key_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+/// Column of parameter listing, selectable with `--columns`
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParameterColumn {
+    Name,
+    Type,
+    Value,
+    Region,
+    Account,
+    Role,
+    Version,
+    LastModified,
+    DataType,
+    ModifiedBy,
+    Tier,
+    KmsKey,
+    Description,
+}
+
+impl ParameterColumn {
+    /// Columns shown when none were selected
+    pub fn defaults() -> Vec<ParameterColumn> {
+        vec![
+            ParameterColumn::Name,
+            ParameterColumn::Type,
+            ParameterColumn::Value,
+            ParameterColumn::Region,
+            ParameterColumn::Account,
+            ParameterColumn::Role,
+        ]
+    }
+
+    /// Whether the column needs metadata not returned with parameter values
+    pub fn needs_metadata(&self) -> bool {
+        matches!(self, ParameterColumn::ModifiedBy | ParameterColumn::Tier | ParameterColumn::KmsKey | ParameterColumn::Description)
+    }
+
+    pub fn header(&self) -> String {
+        match self {
+            ParameterColumn::Name => "Name",
+            ParameterColumn::Type => "Type",
+            ParameterColumn::Value => "Value",
+            ParameterColumn::Region => "Region",
+            ParameterColumn::Account => "Account",
+            ParameterColumn::Role => "Role",
+            ParameterColumn::Version => "Version",
+            ParameterColumn::LastModified => "Last Modified",
+            ParameterColumn::DataType => "Data Type",
+            ParameterColumn::ModifiedBy => "Modified By",
+            ParameterColumn::Tier => "Tier",
+            ParameterColumn::KmsKey => "KMS Key",
+            ParameterColumn::Description => "Description",
+        }.to_string()
+    }
+
+    /// Value of the column for given parameter, `-` when missing
+    pub fn value(&self, parameter: &Parameter) -> String {
+        let value = match self {
+            ParameterColumn::Name => Some(parameter.name.clone()),
+            ParameterColumn::Type => Some(parameter.r#type.clone()),
+            ParameterColumn::Value => Some(parameter.value.clone()),
+            ParameterColumn::Region => Some(parameter.region.clone().unwrap_or("<unknown>".to_string())),
+            ParameterColumn::Account => parameter.account.clone(),
+            ParameterColumn::Role => parameter.role.clone(),
+            ParameterColumn::Version => parameter.version.map(|version| version.to_string()),
+            ParameterColumn::LastModified => parameter.last_modified.clone(),
+            ParameterColumn::DataType => parameter.data_type.clone(),
+            ParameterColumn::ModifiedBy => parameter.modified_by.clone(),
+            ParameterColumn::Tier => parameter.tier.clone(),
+            ParameterColumn::KmsKey => parameter.kms_key_id.clone(),
+            ParameterColumn::Description => parameter.description.clone(),
+        };
+        value.filter(|v| !v.is_empty()).unwrap_or("-".to_string())
+    }
+}
+
+impl std::str::FromStr for ParameterColumn {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().replace('_', "-").as_str() {
+            "name" => Ok(ParameterColumn::Name),
+            "type" => Ok(ParameterColumn::Type),
+            "value" => Ok(ParameterColumn::Value),
+            "region" => Ok(ParameterColumn::Region),
+            "account" => Ok(ParameterColumn::Account),
+            "role" => Ok(ParameterColumn::Role),
+            "version" => Ok(ParameterColumn::Version),
+            "last-modified" => Ok(ParameterColumn::LastModified),
+            "data-type" => Ok(ParameterColumn::DataType),
+            "modified-by" => Ok(ParameterColumn::ModifiedBy),
+            "tier" => Ok(ParameterColumn::Tier),
+            "kms-key" => Ok(ParameterColumn::KmsKey),
+            "description" => Ok(ParameterColumn::Description),
+            _ => Err(format!(
+                "unknown column '{}', expected one of: name, type, value, region, account, role, version, \
+                last-modified, data-type, modified-by, tier, kms-key, description", s
+            )),
+        }
+    }
+}
+
+/// Collection of configuration parameters
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ParameterList {
+    pub parameters: Vec<Parameter>,
+    /// Columns rendered in table output, defaults when empty
+    #[serde(skip)]
+    pub columns: Vec<ParameterColumn>,
+    /// Targets which failed to return parameters
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub failures: Vec<PartialFailure>,
+}
+
+impl ParameterList {
+    pub fn new() -> Self {
+        ParameterList {
+            parameters: Vec::new(),
+            columns: Vec::new(),
+            failures: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, parameter: Parameter) {
+        self.parameters.push(parameter);
+    }
+}
+
+impl FromIterator<Parameter> for ParameterList {
+    fn from_iter<I: IntoIterator<Item = Parameter>>(iter: I) -> Self {
+        let parameters: Vec<Parameter> = iter.into_iter().collect();
+        ParameterList { parameters, columns: Vec::new(), failures: Vec::new() }
+    }
+}
+
+impl Default for ParameterList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Mergeable for ParameterList {
+    fn merge(&mut self, other: Self) {
+        self.parameters.extend(other.parameters);
+        self.failures.extend(other.failures);
+    }
+
+    fn add_failure(&mut self, failure: PartialFailure) {
+        self.failures.push(failure);
+    }
+
+    fn set_account(&mut self, account: &str, role: &str) {
+        for parameter in self.parameters.iter_mut() {
+            parameter.account = Some(account.to_string());
+            parameter.role = Some(role.to_string());
+        }
+        for failure in self.failures.iter_mut() {
+            failure.target = format!("{}/{}", account, failure.target);
+        }
+    }
+}
+
+impl Renderable for ParameterList {
+    type Record = Parameter;
+
+    fn records(&self) -> &[Parameter] {
+        &self.parameters
+    }
+}
+
+/// Parameter together with attributes needed to (re)create it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParameterDefinition {
+    pub name: String,
+    /// `String`, `StringList` or `SecureString`
+    pub r#type: String,
+    pub value: String,
+    /// KMS key encrypting `SecureString` value, default key when missing
+    #[serde(default)]
+    pub kms_key_id: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Tags as key and value
+    #[serde(default)]
+    pub tags: Vec<(String, String)>,
+}
+
+/// Outcome of a write operation on a single parameter
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ParameterChange {
+    pub name: String,
+    /// What happened to the parameter, e.g. `created`, `updated`, `deleted` or `not found`
+    pub action: String,
+    /// Version of the parameter after the change
+    #[serde(default)]
+    pub version: Option<i64>,
+}
+
+/// Result of parameter write operations
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ParameterChangeList {
+    pub changes: Vec<ParameterChange>,
+}
+
+impl Renderable for ParameterChangeList {
+    type Record = ParameterChange;
+
+    fn records(&self) -> &[ParameterChange] {
+        &self.changes
+    }
+}
+
+/// Single version of a parameter
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParameterVersion {
+    pub name: String,
+    pub version: i64,
+    pub r#type: String,
+    pub value: String,
+    /// Time the version was created, in RFC 3339 format
+    #[serde(default)]
+    pub last_modified: Option<String>,
+    /// ARN of the user who created the version
+    #[serde(default)]
+    pub modified_by: Option<String>,
+    #[serde(default)]
+    pub labels: Vec<String>,
+    #[serde(default)]
+    pub kms_key_id: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+/// Versions of a parameter, oldest first
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ParameterHistory {
+    pub versions: Vec<ParameterVersion>,
+}
+
+impl Renderable for ParameterHistory {
+    type Record = ParameterVersion;
+
+    fn records(&self) -> &[ParameterVersion] {
+        &self.versions
+    }
+}
+
+/// Parameters of a remote session to open on an instance
+#[derive(Debug, Clone, Default)]
+pub struct SessionRequest {
+    /// Instance the session is opened on
+    pub target: String,
+    /// Provider document describing the session, interactive shell when missing
+    pub document_name: Option<String>,
+    /// Parameters of the session document
+    pub parameters: BTreeMap<String, Vec<String>>,
+}
+
+/// Session started on an instance, ready to be connected to
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Session {
+    pub session_id: String,
+    /// Token authenticating the stream connection
+    pub token_value: String,
+    /// WebSocket URL of the session stream
+    pub stream_url: String,
+    /// Region the session was started in
+    #[serde(default)]
+    pub region: Option<String>,
+    /// Credentials profile used to start the session
+    #[serde(default)]
+    pub profile: Option<String>,
+}
+
+/// Execution of a shell command on a single instance
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CommandInvocation {
+    pub command_id: String,
+    pub instance_id: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    pub status: String,
+    /// Exit code of the command, once finished
+    #[serde(default)]
+    pub exit_code: Option<i32>,
+    #[serde(default)]
+    pub stdout: String,
+    #[serde(default)]
+    pub stderr: String,
+}
+
+impl CommandInvocation {
+    /// Check whether invocation reached its final status
+    pub fn is_finished(&self) -> bool {
+        !matches!(self.status.as_str(), "Pending" | "InProgress" | "Delayed" | "Cancelling")
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.status == "Success"
+    }
+}
+
+/// Results of a shell command run on several instances
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CommandInvocationList {
+    pub invocations: Vec<CommandInvocation>,
+}
+
+impl Renderable for CommandInvocationList {
+    type Record = CommandInvocation;
+
+    fn records(&self) -> &[CommandInvocation] {
+        &self.invocations
+    }
+}
+
+/// Pick the only instance matching a target, reporting missing or ambiguous matches
+pub fn single_instance(target: &str, mut matches: Vec<Instance>) -> Result<Instance, ProviderError> {
+    match matches.len() {
+        0 => Err(ProviderError::ResourceNotFound(format!("No instance matches '{}'", target))),
+        1 => Ok(matches.remove(0)),
+        _ => Err(ProviderError::AmbiguousTarget {
+            target: target.to_string(),
+            matches: matches.into_iter().map(|instance| instance.instance_id).collect(),
+        }),
+    }
 }
 
-#[allow(dead_code)]
 /// Define list of actions that a single cloud provider should implement
 pub trait ProviderActions {
     /// Identify the current user
-    async fn who_am_i(&self) -> Result<String, ProviderError>;
+    async fn who_am_i(&self) -> Result<Identity, ProviderError>;
     ///// Configure the provider
     //fn configure(&self) -> Result<(), ProviderError>;
     /// List available instances
-    fn list_instances(&self) -> Result<Vec<String>, ProviderError>;
-    /// List defined parameters
-    fn list_parameters(&self) -> Result<Vec<String>, ProviderError>;
+    async fn list_instances(&self, query: &InstanceQuery) -> Result<InstanceList, ProviderError>;
+    /// Find a single instance by its ID or unique name
+    async fn resolve_instance(&self, target: &str) -> Result<Instance, ProviderError> {
+        let instances = self.list_instances(&InstanceQuery::default()).await?;
+        let matches: Vec<Instance> = instances.instances.into_iter()
+            .filter(|instance| instance.instance_id == target || instance.name == target)
+            .collect();
+        single_instance(target, matches)
+    }
+    /// Find instances given by IDs or unique names, plus all instances matching the query
+    async fn resolve_instances(&self, targets: &[String], query: &InstanceQuery) -> Result<InstanceList, ProviderError> {
+        let mut resolved = InstanceList::new();
+        for target in targets {
+            let instance = self.resolve_instance(target).await?;
+            if !resolved.instances.iter().any(|i| i.instance_id == instance.instance_id) {
+                resolved.push(instance);
+            }
+        }
+
+        if query.has_filters() {
+            for instance in self.list_instances(query).await?.instances {
+                if !resolved.instances.iter().any(|i| i.instance_id == instance.instance_id) {
+                    resolved.push(instance);
+                }
+            }
+        }
+        Ok(resolved)
+    }
+    /// Start, stop, reboot or terminate instances, optionally only checking permissions
+    async fn change_state(&self, instance_ids: &[String], action: InstanceAction, dry_run: bool) -> Result<StateChangeList, ProviderError>;
+    /// Describe a single instance in detail
+    async fn describe_instance(&self, instance_id: &str) -> Result<InstanceDetails, ProviderError>;
+    /// Start a remote session on an instance
+    async fn start_session(&self, request: &SessionRequest) -> Result<Session, ProviderError>;
+    /// Terminate a previously started session
+    async fn terminate_session(&self, session_id: &str) -> Result<(), ProviderError>;
+    /// Send shell command to instances, returning pending invocation for each of them
+    async fn send_command(&self, instance_ids: &[String], command: &str, timeout: u64) -> Result<Vec<CommandInvocation>, ProviderError>;
+    /// Fetch current state of command invocation on a single instance
+    async fn get_command_invocation(&self, command_id: &str, instance_id: &str) -> Result<CommandInvocation, ProviderError>;
+    /// List defined parameters, with metadata such as tier, KMS key and description when requested
+    async fn list_parameters(&self, path: Option<String>, decrypt: bool, metadata: bool) -> Result<ParameterList, ProviderError>;
+    /// Read a single parameter given by name, optionally suffixed with `:version` or `:label`
+    async fn get_parameter(&self, selector: &str, decrypt: bool) -> Result<Parameter, ProviderError>;
+    /// Read a single parameter with decrypted value, KMS key, description and tags
+    async fn describe_parameter(&self, name: &str) -> Result<ParameterDefinition, ProviderError>;
+    /// List every version of a parameter, oldest first
+    async fn get_parameter_history(&self, name: &str, decrypt: bool) -> Result<ParameterHistory, ProviderError>;
+    /// Create a parameter, or replace existing one when overwrite is allowed
+    async fn put_parameter(&self, definition: &ParameterDefinition, overwrite: bool) -> Result<ParameterChange, ProviderError>;
+    /// Delete parameters, reporting names which do not exist
+    async fn delete_parameters(&self, names: &[String]) -> Result<ParameterChangeList, ProviderError>;
+    /// List regions available to the caller
+    async fn list_regions(&self) -> Result<Vec<String>, ProviderError>;
+    /// Create a copy of the provider bound to another region
+    fn with_region(&self, region: &str) -> Self where Self: Sized;
+    /// Create a copy of the provider using another credentials profile, in the same region
+    async fn with_profile(&self, profile: &str) -> Self where Self: Sized;
+    /// Create a copy of the provider acting as another role
+    async fn assume_role(&self, role: &str) -> Result<Self, ProviderError> where Self: Sized;
     ///// List container registtries
     //fn list_container_registries(&self) -> Result<Vec<String>, ProviderError>;
 }
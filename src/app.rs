@@ -1,61 +1,409 @@
-use crate::cli::{CLI, Commands, CloudProviders};
-use clap::Parser;
-use crate::actions::{ProviderActions, ProviderError};
+use crate::cli::{CLI, Commands, CloudProviders, ConfigCommands, InstanceCommands, ParamCommands, ParamListArgs, ParameterSort, TunnelCommands};
+use serde::Serialize;
+use crate::accounts::AccountsError;
+use crate::config::{self, Config, ConfigError};
+use crate::actions::{InstanceAction, ParameterDefinition, ProviderActions, ProviderError, SessionRequest};
+use crate::fanout::{fan_out, resolve_accounts, resolve_regions};
+use crate::lifecycle::execute_lifecycle;
+use crate::params::{execute_delete, execute_diff, execute_export, execute_get, execute_history, execute_import, execute_move, execute_put, execute_rollback};
+use crate::run::execute_run;
+use crate::session::{self, SessionError, execute_session};
+use crate::tunnel::{self, execute_tunnels};
 use crate::providers::aws::AwsProvider;
-use log::{info, debug, warn, error};
+use crate::providers::mock::MockProvider;
+use log::{info, debug};
 
-use crate::outputs::table::{Table, TableColumnFormat, TableError};
+use crate::outputs::{self, OutputError};
+use crate::outputs::table::TableError;
 
 #[derive(Debug)]
 pub enum AppError {
     AuthenticationError(String),
-    ConnectionError,
+    ConnectionError(String),
     TimeoutError,
-    PermissionError,
+    PermissionError(String),
     GeneralError(String),
-    OutputTableError(TableError),
+    NotFound(String),
+    ConfigError(ConfigError),
+    OutputError(OutputError),
+    Unsupported(String),
+    /// Error accompanied by a remediation hint for the user
+    WithHint(Box<AppError>, String),
 }
 
 impl std::fmt::Display for AppError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             AppError::AuthenticationError(err) => write!(f, "Authentication error occurred: {}", err),
-            AppError::ConnectionError => write!(f, "Connection error occurred"),
+            AppError::ConnectionError(msg) => write!(f, "Connection error occurred: {}", msg),
             AppError::TimeoutError => write!(f, "Operation timed out"),
-            AppError::PermissionError => write!(f, "Permission denied"),
+            AppError::PermissionError(msg) => write!(f, "Permission denied: {}", msg),
             AppError::GeneralError(msg) => write!(f, "General error: {}", msg),
-            AppError::OutputTableError(msg) => write! (f, "Output error: {}", msg),
+            AppError::NotFound(msg) => write!(f, "Not found: {}", msg),
+            AppError::ConfigError(err) => write!(f, "Configuration error: {}", err),
+            AppError::OutputError(msg) => write! (f, "Output error: {}", msg),
+            AppError::Unsupported(msg) => write!(f, "Unsupported: {}", msg),
+            AppError::WithHint(err, hint) => write!(f, "{}\nHint: {}", err, hint),
         }
     }
 }
 
 impl std::error::Error for AppError {}
 
+/// Structured error written to stderr with `--error-format json`
+#[derive(Debug, Serialize)]
+pub struct ErrorReport {
+    pub kind: &'static str,
+    pub exit_code: u8,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hint: Option<String>,
+}
+
+impl AppError {
+    pub fn with_hint(self, hint: String) -> Self {
+        AppError::WithHint(Box::new(self), hint)
+    }
+
+    /// Process exit code reported for this error
+    ///
+    /// | Code | Kind             |
+    /// |------|------------------|
+    /// | 1    | general          |
+    /// | 2    | usage (by clap)  |
+    /// | 3    | authentication   |
+    /// | 4    | permission       |
+    /// | 5    | connection       |
+    /// | 6    | timeout          |
+    /// | 7    | not_found        |
+    /// | 8    | output           |
+    /// | 9    | unsupported      |
+    /// | 10   | configuration    |
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            AppError::GeneralError(_) => 1,
+            AppError::AuthenticationError(_) => 3,
+            AppError::PermissionError(_) => 4,
+            AppError::ConnectionError(_) => 5,
+            AppError::TimeoutError => 6,
+            AppError::NotFound(_) => 7,
+            AppError::OutputError(_) => 8,
+            AppError::Unsupported(_) => 9,
+            AppError::ConfigError(_) => 10,
+            AppError::WithHint(err, _) => err.exit_code(),
+        }
+    }
+
+    /// Machine readable name of the error kind
+    pub fn kind(&self) -> &'static str {
+        match self {
+            AppError::GeneralError(_) => "general",
+            AppError::AuthenticationError(_) => "authentication",
+            AppError::PermissionError(_) => "permission",
+            AppError::ConnectionError(_) => "connection",
+            AppError::TimeoutError => "timeout",
+            AppError::NotFound(_) => "not_found",
+            AppError::OutputError(_) => "output",
+            AppError::Unsupported(_) => "unsupported",
+            AppError::ConfigError(_) => "configuration",
+            AppError::WithHint(err, _) => err.kind(),
+        }
+    }
+
+    /// Structured representation of the error
+    pub fn report(&self) -> ErrorReport {
+        let (message, hint) = match self {
+            AppError::WithHint(err, hint) => (err.to_string(), Some(hint.clone())),
+            _ => (self.to_string(), None),
+        };
+
+        ErrorReport {
+            kind: self.kind(),
+            exit_code: self.exit_code(),
+            message,
+            hint,
+        }
+    }
+}
+
+/// Format `--profile` argument for hints, omitted for default profile
+fn profile_arg(profile: &Option<String>) -> String {
+    match profile.as_deref() {
+        Some("default") | None => String::new(),
+        Some(profile) => format!(" --profile {}", profile),
+    }
+}
+
 impl From<ProviderError> for AppError {
     fn from(error: ProviderError) -> Self {
+        let message = error.to_string();
         match error {
             ProviderError::ConfigurationError => AppError::GeneralError("Configuration error".to_string()),
             ProviderError::AuthenticationError => AppError::AuthenticationError("Authentication failed".to_string()),
-            ProviderError::ResourceNotFound => AppError::GeneralError("Resource not found".to_string()),
+            ProviderError::ResourceNotFound(msg) => AppError::NotFound(msg),
             ProviderError::GeneralError(msg) => AppError::GeneralError(msg),
             ProviderError::TimeoutError => AppError::TimeoutError,
-            ProviderError::ConnectionError => AppError::ConnectionError,
-            ProviderError::PermissionError => AppError::PermissionError,
+            ProviderError::ConnectionError => AppError::ConnectionError(message),
+            ProviderError::PermissionError => AppError::PermissionError(message),
+            ProviderError::ServiceError {..} => AppError::GeneralError(message),
+
+            ProviderError::AmbiguousTarget {..} => AppError::GeneralError(message)
+                .with_hint("use an ID to select a single resource".to_string()),
+
+            ProviderError::SsoTokenExpired(profile) => AppError::AuthenticationError(message)
+                .with_hint(format!("run `aws sso login{}` and retry", profile_arg(&profile))),
+
+            ProviderError::MissingCredentials(profile) => AppError::AuthenticationError(message)
+                .with_hint(format!("run `aws configure{}` or select another profile with `--profile`", profile_arg(&profile))),
+
+            ProviderError::AccessDenied {action, ..} => AppError::PermissionError(message)
+                .with_hint(match action {
+                    Some(action) => format!("grant `{}` to the identity shown by `ctk whoami`", action),
+                    None => "check policies attached to the identity shown by `ctk whoami`".to_string(),
+                }),
+
+            ProviderError::Throttled {..} => AppError::GeneralError(message)
+                .with_hint("retry later or query fewer regions and accounts at once".to_string()),
+
+            ProviderError::UnknownRegion(region) => AppError::ConnectionError(message)
+                .with_hint(format!("check that `{}` is a valid, enabled region passed with `--region` or `CTK_REGION`", region)),
+
+            ProviderError::EndpointError(_) => AppError::ConnectionError(message)
+                .with_hint("check network connectivity, proxy and VPN settings".to_string()),
+        }
+    }
+}
+
+impl From<OutputError> for AppError {
+    fn from(error: OutputError) -> Self {
+        AppError::OutputError(error)
+    }
+}
+
+impl From<AccountsError> for AppError {
+    fn from(error: AccountsError) -> Self {
+        AppError::GeneralError(error.to_string())
+    }
+}
+
+impl From<ConfigError> for AppError {
+    fn from(error: ConfigError) -> Self {
+        AppError::ConfigError(error)
+    }
+}
+
+impl From<SessionError> for AppError {
+    fn from(error: SessionError) -> Self {
+        let message = error.to_string();
+        match error {
+            SessionError::ConnectionError(_) => AppError::ConnectionError(message),
+            SessionError::Unsupported(_) => AppError::Unsupported(message)
+                .with_hint("install session-manager-plugin or pass `--client plugin`".to_string()),
+            _ => AppError::GeneralError(message),
         }
     }
 }
 
 impl From<TableError> for AppError {
     fn from(error: TableError) -> Self {
-        AppError::OutputTableError(error)
+        AppError::OutputError(OutputError::TableError(error))
+    }
+}
+
+/// Execute selected command using given provider and render its results
+async fn execute<P: ProviderActions>(provider: P, cli: &CLI, config: &Config) -> Result<(), AppError> {
+    match &cli.command {
+        Commands::Whoami => {
+            debug!("Executing 'whoami' command");
+            let user_data = provider.who_am_i().await?;
+            outputs::render(user_data, &cli.output())?;
+        }
+
+        Commands::Instances {action: None, list} => {
+            debug!("Executing 'instances' command");
+            let query = list.query();
+            let query = &query;
+            let regions = resolve_regions(&provider, &list.regions).await?;
+            let accounts = resolve_accounts(&list.accounts)?;
+            let mut instances = fan_out(provider, &accounts, &regions, |p| async move {
+                p.list_instances(query).await
+            }).await?;
+            instances.columns = list.columns.clone();
+            outputs::render(instances, &cli.output())?;
+        }
+
+        Commands::Instances {action: Some(InstanceCommands::Show {target}), ..} => {
+            debug!("Executing 'instances show' command");
+            let instance = provider.resolve_instance(target).await?;
+            let details = provider.describe_instance(&instance.instance_id).await?;
+            outputs::render(details, &cli.output())?;
+        }
+
+        Commands::Instances {action: Some(InstanceCommands::Start(args)), ..} => {
+            debug!("Executing 'instances start' command");
+            execute_lifecycle(&provider, InstanceAction::Start, args, cli).await?;
+        }
+
+        Commands::Instances {action: Some(InstanceCommands::Stop(args)), ..} => {
+            debug!("Executing 'instances stop' command");
+            execute_lifecycle(&provider, InstanceAction::Stop, args, cli).await?;
+        }
+
+        Commands::Instances {action: Some(InstanceCommands::Reboot(args)), ..} => {
+            debug!("Executing 'instances reboot' command");
+            execute_lifecycle(&provider, InstanceAction::Reboot, args, cli).await?;
+        }
+
+        Commands::Instances {action: Some(InstanceCommands::Terminate(args)), ..} => {
+            debug!("Executing 'instances terminate' command");
+            execute_lifecycle(&provider, InstanceAction::Terminate, args, cli).await?;
+        }
+
+        Commands::Params {action: None, list: ParamListArgs {path, decrypt, columns, sort, regions, accounts}} => {
+            debug!("Executing 'params' command");
+            let regions = resolve_regions(&provider, regions).await?;
+            let accounts = resolve_accounts(accounts)?;
+            let metadata = columns.iter().any(|column| column.needs_metadata());
+            let mut data = fan_out(provider, &accounts, &regions, |p| async move {
+                p.list_parameters(path.clone(), *decrypt, metadata).await
+            }).await?;
+
+            match sort {
+                Some(ParameterSort::Name) => data.parameters.sort_by(|a, b| a.name.cmp(&b.name)),
+                // timestamps share one format, newest first and unknown last
+                Some(ParameterSort::LastModified) => data.parameters.sort_by(|a, b| b.last_modified.cmp(&a.last_modified)),
+                None => {}
+            }
+            data.columns = columns.clone();
+            outputs::render(data, &cli.output())?;
+        }
+
+        Commands::Params {action: Some(ParamCommands::Get {name, decrypt, raw}), ..} => {
+            debug!("Executing 'params get' command");
+            execute_get(&provider, name, *decrypt, *raw, cli).await?;
+        }
+
+        Commands::Params {action: Some(ParamCommands::Export {path, format, secret_name}), ..} => {
+            debug!("Executing 'params export' command");
+            execute_export(&provider, path, format, secret_name.as_deref()).await?;
+        }
+
+        Commands::Params {action: Some(ParamCommands::Import {file, prefix, secure_keys, dry_run, yes}), ..} => {
+            debug!("Executing 'params import' command");
+            execute_import(&provider, file, prefix, secure_keys, *dry_run, *yes, cli).await?;
+        }
+
+        Commands::Params {action: Some(ParamCommands::Diff(args)), ..} => {
+            debug!("Executing 'params diff' command");
+            execute_diff(&provider, args, cli).await?;
+        }
+
+        Commands::Params {action: Some(ParamCommands::History {name, decrypt}), ..} => {
+            debug!("Executing 'params history' command");
+            execute_history(&provider, name, *decrypt, cli).await?;
+        }
+
+        Commands::Params {action: Some(ParamCommands::Rollback {name, to}), ..} => {
+            debug!("Executing 'params rollback' command");
+            execute_rollback(&provider, name, *to, cli).await?;
+        }
+
+        Commands::Params {action: Some(ParamCommands::Put {name, value, secure, kms_key, overwrite}), ..} => {
+            debug!("Executing 'params put' command");
+            let definition = ParameterDefinition {
+                name: name.clone(),
+                r#type: if *secure { "SecureString" } else { "String" }.to_string(),
+                value: value.clone(),
+                kms_key_id: kms_key.clone(),
+                description: None,
+                tags: Vec::new(),
+            };
+            execute_put(&provider, &definition, *overwrite, cli).await?;
+        }
+
+        Commands::Params {action: Some(ParamCommands::Delete {names, yes}), ..} => {
+            debug!("Executing 'params delete' command");
+            execute_delete(&provider, names, *yes, cli).await?;
+        }
+
+        Commands::Params {action: Some(ParamCommands::Mv {source, destination, overwrite}), ..} => {
+            debug!("Executing 'params mv' command");
+            execute_move(&provider, source, destination, *overwrite, cli).await?;
+        }
+
+        Commands::Ssh {target, client} => {
+            debug!("Executing 'ssh' command");
+            let instance = provider.resolve_instance(target).await?;
+            let request = SessionRequest {
+                target: instance.instance_id,
+                ..Default::default()
+            };
+            execute_session(&provider, &request, client).await?;
+        }
+
+        Commands::Run(args) => {
+            debug!("Executing 'run' command");
+            execute_run(&provider, args, cli).await?;
+        }
+
+        Commands::Tunnel {action: None, open} => {
+            debug!("Executing 'tunnel' command");
+            let spec = open.spec()
+                .ok_or(AppError::GeneralError("Instance and remote port are required".to_string()))?;
+            execute_tunnels(&provider, &[(spec.target.clone(), spec)], &open.client).await?;
+        }
+
+        Commands::Tunnel {action: Some(TunnelCommands::Up {names, client}), ..} => {
+            debug!("Executing 'tunnel up' command");
+            let tunnels = config.tunnels(names)?;
+            if tunnels.is_empty() {
+                return Err(AppError::NotFound("No tunnels defined in configuration".to_string()));
+            }
+            execute_tunnels(&provider, &tunnels, client).await?;
+        }
+
+        Commands::Config {..} | Commands::SessionServer {..} | Commands::Tunnel {action: Some(TunnelCommands::List), ..} => {
+            return Err(AppError::Unsupported("Command does not use a provider".to_string()));
+        }
     }
+    Ok(())
 }
 
+/// Execute configuration management command
+fn execute_config(action: &ConfigCommands, cli: &CLI, mut config: Config, path: &std::path::Path) -> Result<(), AppError> {
+    match action {
+        ConfigCommands::List => {
+            debug!("Executing 'config list' command");
+            outputs::render(config.list(), &cli.output())?;
+        }
+
+        ConfigCommands::Get {key} => {
+            debug!("Executing 'config get' command");
+            let context = config.context(cli.context.as_deref())?
+                .ok_or(ConfigError::NoActiveContext)?;
+            match context.get(key)? {
+                Some(value) => println!("{}", value),
+                None => return Err(AppError::NotFound(format!("Key '{}' is not set", key))),
+            }
+        }
+
+        ConfigCommands::Set {key, value} => {
+            debug!("Executing 'config set' command");
+            config.context_mut(cli.context.as_deref())?.set(key, value)?;
+            config.save(path)?;
+        }
 
-pub async fn run_app() -> Result<(), AppError> {
-    debug!("Parsing command line arguments...");
-    let cli = CLI::parse();
+        ConfigCommands::UseContext {name} => {
+            debug!("Executing 'config use-context' command");
+            config.use_context(name)?;
+            config.save(path)?;
+            info!("Switched to context {}", name);
+        }
+    }
+    Ok(())
+}
 
+pub async fn run_app(mut cli: CLI) -> Result<(), AppError> {
     let log_level = match cli.verbose {
         0 => "warn",
         1 => "info",
@@ -72,53 +420,49 @@ pub async fn run_app() -> Result<(), AppError> {
     info!("Log level set to: {}", log_level);
     debug!("CLI arguments: {:?}", cli);
 
-    match cli.provider {
-
-         CloudProviders::Aws => {
-            debug!("Selected provider: AWS");
-            let provider = AwsProvider::new();
-
+    let config_path = cli.config.clone().unwrap_or_else(config::default_path);
+    let config = Config::load(&config_path)?;
 
-            //FIXME handle data captures and single table.render invocation
-            match cli.command {
-                Commands::Whoami => {
-                    debug!("Executing 'whoami' command for AWS provider");
-                    let user_data = provider.who_am_i().await?;
-                    let table: Table = user_data.into();
-                    table.render(2)?;
+    if let Commands::Config {action} = &cli.command {
+        return execute_config(action, &cli, config, &config_path);
+    }
 
-                }
+    if let Some(context) = config.context(cli.context.as_deref())? {
+        debug!("Applying context: {:?}", context);
+        cli.apply_context(context);
+    }
 
-                Commands::Instances => {
-                    debug!("Executing 'instances' command for AWS provider");
-                    let instances = provider.list_instances().await?;
-                    let table: Table = instances.into();
-                    table.render(2)?;
+    match &cli.command {
+        Commands::SessionServer {port} => {
+            debug!("Executing 'session-server' command");
+            let listener = tokio::net::TcpListener::bind(("127.0.0.1", *port)).await
+                .map_err(SessionError::from)?;
+            return Ok(session::server::serve(listener).await?);
+        }
 
-                }
+        Commands::Tunnel {action: Some(TunnelCommands::List), ..} => {
+            debug!("Executing 'tunnel list' command");
+            let tunnels = tunnel::list_active().map_err(SessionError::from)?;
+            return Ok(outputs::render(tunnels, &cli.output())?);
+        }
 
-                Commands::Params {path, decrypt} => {
-                    let data = provider.list_parameters(path, decrypt).await?;
-                    let table: Table = data.into();
-                    table.render(2)?;
-                }
+        _ => {}
+    }
 
-                _ => {
-                    warn!("Command not exists or not-yet implemented");
-                    return Err(AppError::GeneralError("Command not yet implemented".to_string()));
-                }
-            }
-         }
+    match cli.provider() {
+        CloudProviders::Aws => {
+            debug!("Selected provider: AWS");
+            let provider = AwsProvider::new(cli.profile.clone(), cli.region.clone()).await;
+            execute(provider, &cli, &config).await?;
+        }
 
-         _ => {
-            error!("Selected provider is not supported yet.");
-            return Err(AppError::GeneralError("Provider not supported".to_string()));
+        CloudProviders::Mock => {
+            debug!("Selected provider: Mock");
+            let provider = MockProvider::new(cli.fixtures.clone());
+            execute(provider, &cli, &config).await?;
         }
     };
 
-
-    //TODO move all display logic outside match
-
     debug!("Finished executing command.");
     // Application logic goes here
     Ok(())
@@ -1,4 +1,9 @@
-use clap::{Parser, Subcommand, ValueEnum};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use crate::actions::{InstanceColumn, InstanceQuery, ParameterColumn};
+use crate::config::Context;
+use crate::tunnel::TunnelSpec;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
 
 #[derive(Parser, Debug)]
 #[command(name = "ctk")]
@@ -6,41 +11,146 @@ use clap::{Parser, Subcommand, ValueEnum};
 #[command(author,version,about)]
 pub struct CLI {
     // NOTE env value for this decorator is avilable to pass provider as ENV value - add it later
-    #[arg(short, long, value_enum, default_value_t = CloudProviders::Aws)]
-    pub provider: CloudProviders,
+    /// Cloud provider, defaults to the active context's provider or AWS
+    #[arg(short, long, value_enum)]
+    pub provider: Option<CloudProviders>,
+
+    /// Path of the configuration file
+    #[arg(long, global = true, env = "CTK_CONFIG")]
+    pub config: Option<PathBuf>,
+
+    /// Context used instead of the active one
+    #[arg(long, global = true, env = "CTK_CONTEXT")]
+    pub context: Option<String>,
+
+    /// Named credentials profile used by the provider
+    #[arg(long, global = true, env = "CTK_PROFILE")]
+    pub profile: Option<String>,
+
+    /// Region used by the provider
+    #[arg(long, global = true, env = "CTK_REGION")]
+    pub region: Option<String>,
+
+    /// Directory with JSON fixtures used by the mock provider
+    #[arg(long, env = "CTK_FIXTURES", default_value = "fixtures")]
+    pub fixtures: PathBuf,
 
     #[arg(short, long, action = clap::ArgAction::Count)]
     pub verbose: u8,
 
-    // TODO: add global config for output
+    /// Output format used to display command results, defaults to the active context's format or table
+    #[arg(short, long, global = true, value_enum)]
+    pub output: Option<OutputFormat>,
+
+    /// Format of errors written to stderr
+    #[arg(long, global = true, value_enum, default_value_t = ErrorFormat::Text)]
+    pub error_format: ErrorFormat,
 
     #[command(subcommand)]
     pub command: Commands,
 }
 
-#[derive(Clone, Debug, clap::ValueEnum)]
+#[derive(Clone, Debug, clap::ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum CloudProviders {
     /// Amazon Web Services
     Aws,
-     
+    /// Local fixtures, for offline demos and tests
+    Mock,
+}
+
+#[derive(Clone, Debug, clap::ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// Column-aligned table
+    Table,
+    /// Pretty-printed JSON
+    Json,
+    /// YAML document
+    Yaml,
+    /// Comma-separated values
+    Csv,
+    /// Tab-separated values
+    Tsv,
+}
+
+impl CLI {
+    /// Fill options not given on the command line with values from the context
+    pub fn apply_context(&mut self, context: &Context) {
+        self.provider = self.provider.take().or(context.provider.clone());
+        self.profile = self.profile.take().or(context.profile.clone());
+        self.region = self.region.take().or(context.region.clone());
+        self.output = self.output.take().or(context.output.clone());
+
+        if let Commands::Params { action: None, list } = &mut self.command {
+            list.path = list.path.take().or(context.ssm_path.clone());
+        }
+    }
+
+    /// Selected provider
+    pub fn provider(&self) -> CloudProviders {
+        self.provider.clone().unwrap_or(CloudProviders::Aws)
+    }
+
+    /// Selected output format
+    pub fn output(&self) -> OutputFormat {
+        self.output.clone().unwrap_or(OutputFormat::Table)
+    }
 }
 
 #[derive(Subcommand, Debug)]
 pub enum Commands{
-    ///// Configure CTK for selected provider
-    //Config,
+    /// Manage configuration contexts
+    Config {
+        #[command(subcommand)]
+        action: ConfigCommands,
+    },
     ///// Show available providers
     //Providers,
     /// Show cloud instances
-    Instances,
-    /// Show parameters
+    #[command(alias = "instance", args_conflicts_with_subcommands = true)]
+    Instances {
+        #[command(subcommand)]
+        action: Option<InstanceCommands>,
+
+        #[command(flatten)]
+        list: InstanceListArgs,
+    },
+    /// Show and manage parameters
+    #[command(alias = "param", args_conflicts_with_subcommands = true)]
     Params {
-        /// Parameter path
-        path: Option<String>,
+        #[command(subcommand)]
+        action: Option<ParamCommands>,
 
-        /// Flag to allow decryption of secure parameters
-        #[arg(short, long, default_value_t = false)]
-        decrypt: bool,
+        #[command(flatten)]
+        list: ParamListArgs,
+    },
+    /// Open an interactive shell on an instance through Session Manager
+    Ssh {
+        /// Instance ID or unique name
+        target: String,
+
+        /// Client driving the session
+        #[arg(long, value_enum, default_value_t = SessionClient::Auto)]
+        client: SessionClient,
+    },
+    /// Forward local ports through an instance via Session Manager
+    #[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+    Tunnel {
+        #[command(subcommand)]
+        action: Option<TunnelCommands>,
+
+        #[command(flatten)]
+        open: TunnelArgs,
+    },
+    /// Run a shell command on instances through SSM Run Command
+    Run(RunArgs),
+    /// Serve stand-in sessions locally, for use with the mock provider
+    #[command(hide = true)]
+    SessionServer {
+        /// Port to listen on
+        #[arg(long, default_value_t = 8765)]
+        port: u16,
     },
     ///// Show container registries
     //Creg, 
@@ -48,4 +158,485 @@ pub enum Commands{
     Whoami,
 }
 
+#[derive(Clone, Debug, clap::ValueEnum)]
+pub enum ErrorFormat {
+    /// Human readable message
+    Text,
+    /// Single-line JSON object with kind, exit code, message and hint
+    Json,
+}
+
+#[derive(Clone, Debug, clap::ValueEnum)]
+pub enum ExportFormat {
+    /// `KEY=value` lines for .env files
+    Dotenv,
+    /// `export KEY='value'` lines for POSIX shells
+    Sh,
+    /// JSON object
+    Json,
+    /// Kubernetes Secret manifest
+    K8sSecret,
+}
+
+#[derive(Clone, Debug, clap::ValueEnum)]
+pub enum ParameterSort {
+    /// Alphabetically by name
+    Name,
+    /// Most recently modified first
+    LastModified,
+}
+
+#[derive(Clone, Debug, clap::ValueEnum)]
+pub enum SessionClient {
+    /// Hand off to session-manager-plugin when installed, use native client otherwise
+    Auto,
+    /// Built-in WebSocket client
+    Native,
+    /// Official session-manager-plugin
+    Plugin,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigCommands {
+    /// List defined contexts
+    List,
+    /// Show a value of the selected context
+    Get {
+        /// One of: provider, profile, region, output, ssm_path
+        key: String,
+    },
+    /// Set a value in the selected context, creating it if missing
+    Set {
+        /// One of: provider, profile, region, output, ssm_path
+        key: String,
+        value: String,
+    },
+    /// Make a context the active one
+    UseContext {
+        name: String,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum InstanceCommands {
+    /// Show details of a single instance
+    Show {
+        /// Instance ID or unique name
+        target: String,
+    },
+    /// Start stopped instances
+    Start(LifecycleArgs),
+    /// Stop running instances
+    Stop(LifecycleArgs),
+    /// Reboot running instances
+    Reboot(LifecycleArgs),
+    /// Terminate instances
+    Terminate(LifecycleArgs),
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ParamCommands {
+    /// Show a single parameter
+    Get {
+        /// Parameter name, optionally followed by `:version` or `:label`
+        name: String,
+
+        /// Flag to allow decryption of secure parameter
+        #[arg(short, long, default_value_t = false)]
+        decrypt: bool,
+
+        /// Print only the value, for use in scripts
+        #[arg(long, default_value_t = false)]
+        raw: bool,
+    },
+    /// Print parameters under a path as environment variables
+    Export {
+        /// Parameter path, stripped from variable names
+        path: String,
+
+        /// Format of exported variables
+        #[arg(long, value_enum, default_value_t = ExportFormat::Dotenv)]
+        format: ExportFormat,
+
+        /// Name of Kubernetes Secret, derived from the path by default
+        #[arg(long)]
+        secret_name: Option<String>,
+    },
+    /// Write variables from a dotenv, JSON or YAML file as parameters under a prefix
+    Import {
+        /// File with variables, format is chosen by extension (`.json`, `.yaml`, dotenv otherwise)
+        file: PathBuf,
+
+        /// Path the parameters are written to
+        #[arg(long)]
+        prefix: String,
+
+        /// Comma-separated variable name patterns stored as SecureString (e.g. 'PASSWORD,*_TOKEN')
+        #[arg(long, value_delimiter = ',')]
+        secure_keys: Vec<String>,
+
+        /// Only show planned changes
+        #[arg(long, default_value_t = false)]
+        dry_run: bool,
+
+        /// Skip confirmation prompt
+        #[arg(short, long, default_value_t = false)]
+        yes: bool,
+    },
+    /// Compare parameters under two paths, failing when they differ
+    Diff(ParamDiffArgs),
+    /// List versions of a parameter
+    History {
+        /// Parameter name
+        name: String,
+
+        /// Flag to allow decryption of secure values
+        #[arg(short, long, default_value_t = false)]
+        decrypt: bool,
+    },
+    /// Restore value of an earlier version as a new version
+    Rollback {
+        /// Parameter name
+        name: String,
+
+        /// Version whose value is restored
+        #[arg(long)]
+        to: i64,
+    },
+    /// Create or update a parameter
+    Put {
+        /// Parameter name
+        name: String,
+
+        /// Parameter value
+        value: String,
+
+        /// Store the value encrypted, as SecureString
+        #[arg(long, default_value_t = false)]
+        secure: bool,
+
+        /// KMS key encrypting the value, the account default key when omitted
+        #[arg(long, requires = "secure")]
+        kms_key: Option<String>,
+
+        /// Replace value of existing parameter
+        #[arg(long, default_value_t = false)]
+        overwrite: bool,
+    },
+    /// Delete parameters
+    Delete {
+        /// Parameter names
+        #[arg(required = true)]
+        names: Vec<String>,
+
+        /// Skip confirmation prompt
+        #[arg(short, long, default_value_t = false)]
+        yes: bool,
+    },
+    /// Rename a parameter, keeping its type, KMS key, description and tags
+    Mv {
+        /// Current parameter name
+        source: String,
+
+        /// New parameter name
+        destination: String,
+
+        /// Replace destination parameter if it exists
+        #[arg(long, default_value_t = false)]
+        overwrite: bool,
+    },
+}
+
+#[derive(Args, Debug)]
+pub struct ParamDiffArgs {
+    /// Path compared against
+    pub left: String,
+
+    /// Path compared with the left one
+    pub right: String,
+
+    /// Show values of secure parameters, which are compared either way
+    #[arg(short, long, default_value_t = false)]
+    pub decrypt: bool,
+
+    /// Profile used to read the left path
+    #[arg(long)]
+    pub left_profile: Option<String>,
+
+    /// Profile used to read the right path
+    #[arg(long)]
+    pub right_profile: Option<String>,
+
+    /// Region of the left path
+    #[arg(long)]
+    pub left_region: Option<String>,
+
+    /// Region of the right path
+    #[arg(long)]
+    pub right_region: Option<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct ParamListArgs {
+    /// Parameter path
+    pub path: Option<String>,
+
+    /// Flag to allow decryption of secure parameters
+    #[arg(short, long, default_value_t = false)]
+    pub decrypt: bool,
+
+    /// Comma-separated columns shown in table output (e.g. name,version,last-modified,tier,kms-key)
+    #[arg(long, value_delimiter = ',')]
+    pub columns: Vec<ParameterColumn>,
+
+    /// Order of listed parameters, as returned by the provider by default
+    #[arg(long, value_enum)]
+    pub sort: Option<ParameterSort>,
+
+    #[command(flatten)]
+    pub regions: RegionArgs,
+
+    #[command(flatten)]
+    pub accounts: AccountArgs,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum TunnelCommands {
+    /// Open tunnels defined in configuration, all of them when no name is given
+    Up {
+        /// Names of `[tunnels.<name>]` configuration sections
+        names: Vec<String>,
+
+        /// Client forwarding the traffic
+        #[arg(long, value_enum, default_value_t = SessionClient::Auto)]
+        client: SessionClient,
+    },
+    /// Show tunnels opened by running ctk processes
+    List,
+}
+
+#[derive(Args, Debug)]
+pub struct TunnelArgs {
+    /// Instance ID or unique name the traffic goes through
+    #[arg(required = true)]
+    pub target: Option<String>,
+
+    /// Local port to listen on, defaults to remote port
+    #[arg(long = "local")]
+    pub local_port: Option<u16>,
+
+    /// Host reachable from the instance, the instance itself when omitted
+    #[arg(long)]
+    pub remote_host: Option<String>,
+
+    /// Port on the remote host
+    #[arg(long, required = true)]
+    pub remote_port: Option<u16>,
+
+    /// Client forwarding the traffic
+    #[arg(long, value_enum, default_value_t = SessionClient::Auto)]
+    pub client: SessionClient,
+}
+
+impl TunnelArgs {
+    /// Tunnel given on the command line, if complete
+    pub fn spec(&self) -> Option<TunnelSpec> {
+        Some(TunnelSpec {
+            target: self.target.clone()?,
+            local_port: self.local_port,
+            remote_host: self.remote_host.clone(),
+            remote_port: self.remote_port?,
+        })
+    }
+}
+
+#[derive(Args, Debug)]
+pub struct LifecycleArgs {
+    /// Instance IDs or unique names
+    pub targets: Vec<String>,
+
+    #[command(flatten)]
+    pub filters: InstanceFilterArgs,
+
+    /// Skip confirmation prompt
+    #[arg(short, long, default_value_t = false)]
+    pub yes: bool,
+
+    /// Only check whether the action is permitted, without performing it
+    #[arg(long, default_value_t = false)]
+    pub dry_run: bool,
+
+    /// Wait until instances reach the target state
+    #[arg(short, long, default_value_t = false)]
+    pub wait: bool,
+
+    /// Maximum time to wait, in seconds
+    #[arg(long, default_value_t = 600)]
+    pub timeout: u64,
+}
+
+impl LifecycleArgs {
+    /// Build provider query from filter options
+    pub fn query(&self) -> InstanceQuery {
+        self.filters.query()
+    }
+}
+
+#[derive(Args, Debug)]
+pub struct RunArgs {
+    /// Instance IDs, unique names or `tag:key=value[,value...]` selectors
+    #[arg(long, required = true, num_args = 1.., value_parser = parse_run_target)]
+    pub targets: Vec<RunTarget>,
+
+    /// Maximum execution time of the command, in seconds
+    #[arg(long, default_value_t = 600)]
+    pub timeout: u64,
+
+    /// Shell command to run, given after `--`
+    #[arg(last = true, required = true)]
+    pub command: Vec<String>,
+}
+
+impl RunArgs {
+    /// Split targets into explicit instances and query for running instances with given tags
+    pub fn selection(&self) -> (Vec<String>, InstanceQuery) {
+        let mut instances = Vec::new();
+        let mut query = InstanceQuery::default();
+        for target in &self.targets {
+            match target {
+                RunTarget::Instance(instance) => instances.push(instance.clone()),
+                RunTarget::Tag(key, value) => query.tags.push((key.clone(), value.clone())),
+            }
+        }
+
+        // commands can be delivered only to running instances
+        if query.has_filters() {
+            query.states = vec!["running".to_string()];
+        }
+        (instances, query)
+    }
+}
+
+/// Single `--targets` entry of `ctk run`
+#[derive(Clone, Debug)]
+pub enum RunTarget {
+    /// Instance ID or unique name
+    Instance(String),
+    /// Tag key and accepted values
+    Tag(String, String),
+}
+
+/// Parse `tag:key=value` selector or plain instance ID/name
+fn parse_run_target(arg: &str) -> Result<RunTarget, String> {
+    match arg.strip_prefix("tag:") {
+        Some(tag) => parse_key_value(tag).map(|(key, value)| RunTarget::Tag(key, value)),
+        None => Ok(RunTarget::Instance(arg.to_string())),
+    }
+}
+
+#[derive(Args, Debug)]
+pub struct InstanceListArgs {
+    /// Maximum number of instances to list
+    #[arg(long)]
+    pub max_items: Option<usize>,
+
+    /// Number of instances fetched per API call
+    #[arg(long, value_parser = clap::value_parser!(i32).range(5..=1000))]
+    pub page_size: Option<i32>,
+
+    /// Comma-separated columns shown in table output (e.g. name,id,type,az,tag:Team)
+    #[arg(long, value_delimiter = ',')]
+    pub columns: Vec<InstanceColumn>,
+
+    #[command(flatten)]
+    pub filters: InstanceFilterArgs,
+
+    #[command(flatten)]
+    pub regions: RegionArgs,
+
+    #[command(flatten)]
+    pub accounts: AccountArgs,
+}
+
+impl InstanceListArgs {
+    /// Build provider query from listing options
+    pub fn query(&self) -> InstanceQuery {
+        InstanceQuery {
+            max_items: self.max_items,
+            page_size: self.page_size,
+            ..self.filters.query()
+        }
+    }
+}
+
+#[derive(Args, Debug)]
+pub struct InstanceFilterArgs {
+    /// Server-side provider filter as `name=value[,value...]`, may be repeated
+    #[arg(long = "filter", value_parser = parse_key_value)]
+    pub filters: Vec<(String, String)>,
+
+    /// Required tag as `key=value[,value...]`, may be repeated
+    #[arg(long = "tag", value_parser = parse_key_value)]
+    pub tags: Vec<(String, String)>,
+
+    /// Accepted instance state (e.g. running, stopped), may be repeated
+    #[arg(long = "state", value_delimiter = ',')]
+    pub states: Vec<String>,
+
+    /// Glob pattern matched against instance name (e.g. 'web-*')
+    #[arg(long)]
+    pub name: Option<String>,
+
+    /// VPC the instances must belong to
+    #[arg(long)]
+    pub vpc: Option<String>,
+
+    /// Subnet the instances must belong to
+    #[arg(long)]
+    pub subnet: Option<String>,
+}
 
+impl InstanceFilterArgs {
+    /// Build provider query from filter options
+    pub fn query(&self) -> InstanceQuery {
+        InstanceQuery {
+            filters: self.filters.clone(),
+            tags: self.tags.clone(),
+            states: self.states.clone(),
+            name: self.name.clone(),
+            vpc: self.vpc.clone(),
+            subnet: self.subnet.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Parse `key=value` argument
+fn parse_key_value(arg: &str) -> Result<(String, String), String> {
+    match arg.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+        _ => Err(format!("expected key=value, got '{}'", arg)),
+    }
+}
+
+#[derive(Args, Debug)]
+pub struct RegionArgs {
+    /// Comma-separated list of regions queried concurrently
+    #[arg(long, value_delimiter = ',', conflicts_with = "all_regions")]
+    pub regions: Vec<String>,
+
+    /// Query every region available to the caller
+    #[arg(long, default_value_t = false)]
+    pub all_regions: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct AccountArgs {
+    /// Role ARN assumed to query another account, may be repeated
+    #[arg(long, value_delimiter = ',')]
+    pub assume_role: Vec<String>,
+
+    /// TOML file declaring `[[accounts]]` with `role_arn` (and optional `name`, `via`) to query
+    #[arg(long, env = "CTK_ACCOUNTS_FILE")]
+    pub accounts_file: Option<PathBuf>,
+}
@@ -0,0 +1,237 @@
+use log::debug;
+use serde::{Deserialize, Serialize};
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use clap::ValueEnum;
+
+use crate::cli::{CloudProviders, OutputFormat};
+use crate::outputs::Renderable;
+use crate::tunnel::TunnelSpec;
+
+/// Keys accepted by `ctk config get/set`
+pub const CONTEXT_KEYS: [&str; 5] = ["provider", "profile", "region", "output", "ssm_path"];
+
+#[derive(Debug)]
+pub enum ConfigError {
+    IOError(io::Error),
+    ParseError(String),
+    SerializationError(String),
+    UnknownKey(String),
+    InvalidValue(String, String),
+    ContextNotFound(String),
+    NoActiveContext,
+    TunnelNotFound(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::IOError(err) => write!(f, "Cannot access configuration file: {}", err),
+            ConfigError::ParseError(msg) => write!(f, "Invalid configuration file: {}", msg),
+            ConfigError::SerializationError(msg) => write!(f, "Cannot serialize configuration: {}", msg),
+            ConfigError::UnknownKey(key) => write!(f, "Unknown key '{}', expected one of: {}", key, CONTEXT_KEYS.join(", ")),
+            ConfigError::InvalidValue(key, value) => write!(f, "Invalid value '{}' for key '{}'", value, key),
+            ConfigError::ContextNotFound(name) => write!(f, "Context '{}' does not exist", name),
+            ConfigError::NoActiveContext => write!(f, "No context selected, use `ctk config use-context <name>`"),
+            ConfigError::TunnelNotFound(name) => write!(f, "Tunnel '{}' is not defined", name),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl From<io::Error> for ConfigError {
+    fn from(err: io::Error) -> Self {
+        ConfigError::IOError(err)
+    }
+}
+
+/// Named set of defaults applied to every command
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Context {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub provider: Option<CloudProviders>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<OutputFormat>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ssm_path: Option<String>,
+}
+
+impl Context {
+    /// Read single value by its key
+    pub fn get(&self, key: &str) -> Result<Option<String>, ConfigError> {
+        let value = match key {
+            "provider" => self.provider.as_ref().map(value_name),
+            "profile" => self.profile.clone(),
+            "region" => self.region.clone(),
+            "output" => self.output.as_ref().map(value_name),
+            "ssm_path" => self.ssm_path.clone(),
+            _ => return Err(ConfigError::UnknownKey(key.to_string())),
+        };
+        Ok(value)
+    }
+
+    /// Set single value by its key, validating enumerated values
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), ConfigError> {
+        let invalid = || ConfigError::InvalidValue(key.to_string(), value.to_string());
+        match key {
+            "provider" => self.provider = Some(CloudProviders::from_str(value, true).map_err(|_| invalid())?),
+            "profile" => self.profile = Some(value.to_string()),
+            "region" => self.region = Some(value.to_string()),
+            "output" => self.output = Some(OutputFormat::from_str(value, true).map_err(|_| invalid())?),
+            "ssm_path" => self.ssm_path = Some(value.to_string()),
+            _ => return Err(ConfigError::UnknownKey(key.to_string())),
+        }
+        Ok(())
+    }
+}
+
+/// Content of the persistent configuration file
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Config {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current_context: Option<String>,
+    #[serde(default)]
+    pub contexts: BTreeMap<String, Context>,
+    /// Port forwarding tunnels opened with `ctk tunnel up`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub tunnels: BTreeMap<String, TunnelSpec>,
+}
+
+impl Config {
+    /// Load configuration, missing file is treated as empty configuration
+    pub fn load(path: &Path) -> Result<Config, ConfigError> {
+        debug!("Loading configuration from {}", path.display());
+        if !path.exists() {
+            debug!("Configuration file does not exist, using defaults");
+            return Ok(Config::default());
+        }
+
+        let content = fs::read_to_string(path)?;
+        toml::from_str(&content).map_err(|e| ConfigError::ParseError(e.to_string()))
+    }
+
+    /// Write configuration, creating parent directories if needed
+    pub fn save(&self, path: &Path) -> Result<(), ConfigError> {
+        debug!("Saving configuration to {}", path.display());
+        let content = toml::to_string_pretty(self)
+            .map_err(|e| ConfigError::SerializationError(e.to_string()))?;
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, content)?;
+        Ok(())
+    }
+
+    /// Context selected explicitly, or the active one
+    pub fn context(&self, name: Option<&str>) -> Result<Option<&Context>, ConfigError> {
+        match name.or(self.current_context.as_deref()) {
+            Some(name) => self.contexts.get(name)
+                .map(Some)
+                .ok_or(ConfigError::ContextNotFound(name.to_string())),
+            None => Ok(None),
+        }
+    }
+
+    /// Mutable context selected explicitly or the active one, created if missing
+    pub fn context_mut(&mut self, name: Option<&str>) -> Result<&mut Context, ConfigError> {
+        let name = name
+            .or(self.current_context.as_deref())
+            .ok_or(ConfigError::NoActiveContext)?
+            .to_string();
+        Ok(self.contexts.entry(name).or_default())
+    }
+
+    /// Make given context the active one
+    pub fn use_context(&mut self, name: &str) -> Result<(), ConfigError> {
+        if !self.contexts.contains_key(name) {
+            return Err(ConfigError::ContextNotFound(name.to_string()));
+        }
+        self.current_context = Some(name.to_string());
+        Ok(())
+    }
+
+    /// Tunnels selected by name, every defined tunnel when no names are given
+    pub fn tunnels(&self, names: &[String]) -> Result<Vec<(String, TunnelSpec)>, ConfigError> {
+        if names.is_empty() {
+            return Ok(self.tunnels.iter()
+                .map(|(name, spec)| (name.clone(), spec.clone()))
+                .collect());
+        }
+
+        names.iter()
+            .map(|name| self.tunnels.get(name)
+                .map(|spec| (name.clone(), spec.clone()))
+                .ok_or(ConfigError::TunnelNotFound(name.clone())))
+            .collect()
+    }
+
+    /// Summary of every defined context
+    pub fn list(&self) -> ContextList {
+        ContextList {
+            contexts: self.contexts.iter()
+                .map(|(name, context)| ContextSummary {
+                    current: self.current_context.as_deref() == Some(name.as_str()),
+                    name: name.clone(),
+                    provider: context.provider.as_ref().map(value_name),
+                    profile: context.profile.clone(),
+                    region: context.region.clone(),
+                    output: context.output.as_ref().map(value_name),
+                    ssm_path: context.ssm_path.clone(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Single row of `ctk config list`
+#[derive(Debug, Serialize)]
+pub struct ContextSummary {
+    pub current: bool,
+    pub name: String,
+    pub provider: Option<String>,
+    pub profile: Option<String>,
+    pub region: Option<String>,
+    pub output: Option<String>,
+    pub ssm_path: Option<String>,
+}
+
+/// Result of `ctk config list`
+#[derive(Debug, Serialize)]
+pub struct ContextList {
+    pub contexts: Vec<ContextSummary>,
+}
+
+impl Renderable for ContextList {
+    type Record = ContextSummary;
+
+    fn records(&self) -> &[ContextSummary] {
+        &self.contexts
+    }
+}
+
+/// Default location of configuration file (`$XDG_CONFIG_HOME/ctk/config.toml` or `~/.config/ctk/config.toml`)
+pub fn default_path() -> PathBuf {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .unwrap_or(PathBuf::from("."));
+    base.join("ctk").join("config.toml")
+}
+
+/// Name of the enum value as accepted on the command line
+fn value_name<T: ValueEnum>(value: &T) -> String {
+    value.to_possible_value()
+        .map(|v| v.get_name().to_string())
+        .unwrap_or_default()
+}
@@ -0,0 +1,126 @@
+use crate::accounts::{AccountTarget, load_accounts};
+use crate::actions::{Mergeable, PartialFailure, ProviderActions, ProviderError};
+use crate::app::AppError;
+use crate::cli::{AccountArgs, RegionArgs};
+use futures::future::join_all;
+use log::{debug, warn};
+
+/// Resolve regions requested by the user, empty list means provider's default region
+pub async fn resolve_regions<P: ProviderActions>(provider: &P, args: &RegionArgs) -> Result<Vec<String>, AppError> {
+    if args.all_regions {
+        debug!("Resolving all available regions");
+        return Ok(provider.list_regions().await?);
+    }
+    Ok(args.regions.clone())
+}
+
+/// Resolve accounts requested by the user, empty list means current credentials only
+pub fn resolve_accounts(args: &AccountArgs) -> Result<Vec<AccountTarget>, AppError> {
+    let mut accounts: Vec<AccountTarget> = match &args.accounts_file {
+        Some(path) => load_accounts(path)?,
+        None => Vec::new(),
+    };
+    accounts.extend(args.assume_role.iter().cloned().map(AccountTarget::new));
+    Ok(accounts)
+}
+
+/// Run the same action against every account and region concurrently and merge results
+///
+/// Failure of a single target is logged and recorded in the result instead of
+/// aborting the whole listing; an error is returned only when no target succeeded.
+pub async fn fan_out<P, T, F, Fut>(provider: P, accounts: &[AccountTarget], regions: &[String], action: F) -> Result<T, AppError>
+where
+    P: ProviderActions,
+    T: Mergeable,
+    F: Fn(P) -> Fut,
+    Fut: Future<Output = Result<T, ProviderError>>,
+{
+    if accounts.is_empty() {
+        return fan_out_regions(provider, regions, &action).await;
+    }
+
+    debug!("Querying accounts: {:?}", accounts);
+    let results = join_all(accounts.iter().map(|account| {
+        let provider = &provider;
+        let action = &action;
+        async move {
+            let assumed = assume_chain(provider, account).await?;
+            let mut data: T = fan_out_regions(assumed, regions, action).await?;
+            data.set_account(&account.account(), &account.role());
+            Ok::<T, AppError>(data)
+        }
+    })).await;
+
+    merge(accounts.iter().map(|account| account.label()).zip(results).collect(), "account")
+}
+
+/// Run the action in every region, or in provider's default one when none were selected
+async fn fan_out_regions<P, T, F, Fut>(provider: P, regions: &[String], action: &F) -> Result<T, AppError>
+where
+    P: ProviderActions,
+    T: Mergeable,
+    F: Fn(P) -> Fut,
+    Fut: Future<Output = Result<T, ProviderError>>,
+{
+    if regions.is_empty() {
+        debug!("No regions requested, using provider default");
+        return Ok(action(provider).await?);
+    }
+
+    debug!("Querying regions: {:?}", regions);
+    let results = join_all(regions.iter().map(|region| {
+        let provider = provider.with_region(region);
+        async move { action(provider).await.map_err(AppError::from) }
+    })).await;
+
+    merge(regions.iter().cloned().zip(results).collect(), "region")
+}
+
+/// Assume every role of the account chain in order
+async fn assume_chain<P: ProviderActions>(provider: &P, account: &AccountTarget) -> Result<P, ProviderError> {
+    let mut assumed: Option<P> = None;
+    for role in account.chain() {
+        let current = assumed.as_ref().unwrap_or(provider);
+        let next = current.assume_role(role).await?;
+        assumed = Some(next);
+    }
+    assumed.ok_or(ProviderError::ConfigurationError)
+}
+
+/// Merge per-target results, recording failed targets alongside successful ones
+fn merge<T: Mergeable>(results: Vec<(String, Result<T, AppError>)>, kind: &str) -> Result<T, AppError> {
+    let total = results.len();
+    let mut merged = T::default();
+    let mut first_error: Option<AppError> = None;
+    let mut not_found = 0;
+    let mut failed = 0;
+
+    for (target, result) in results {
+        match result {
+            Ok(data) => merged.merge(data),
+            Err(AppError::NotFound(msg)) => {
+                debug!("Nothing found in {} {}: {}", kind, target, msg);
+                not_found += 1;
+            }
+            Err(e) => {
+                warn!("Failed to query {} {}: {}", kind, target, e);
+                failed += 1;
+                merged.add_failure(PartialFailure {
+                    target,
+                    error: e.to_string(),
+                });
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+
+    if failed == total {
+        if let Some(e) = first_error {
+            return Err(e);
+        }
+    }
+    if not_found + failed == total {
+        return Err(AppError::NotFound(format!("Nothing found in selected {}s", kind)));
+    }
+    Ok(merged)
+}
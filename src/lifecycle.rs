@@ -0,0 +1,98 @@
+use crate::actions::{InstanceAction, InstanceQuery, ProviderActions};
+use crate::app::AppError;
+use crate::cli::{CLI, LifecycleArgs};
+use crate::outputs::{self, OutputError};
+use crate::outputs::table::Table;
+use crate::prompt::confirm;
+use console::Term;
+use log::{info, debug};
+
+use std::time::{Duration, Instant};
+
+/// Delay between subsequent state checks while waiting
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Resolve target instances, confirm and perform lifecycle action on them
+pub async fn execute_lifecycle<P: ProviderActions>(provider: &P, action: InstanceAction, args: &LifecycleArgs, cli: &CLI) -> Result<(), AppError> {
+    let query = args.query();
+    if args.targets.is_empty() && !query.has_filters() {
+        return Err(AppError::GeneralError("No instances selected, pass instance IDs, names or filters".to_string()));
+    }
+
+    debug!("Resolving instances to {}", action);
+    let instances = provider.resolve_instances(&args.targets, &query).await?;
+    if instances.instances.is_empty() {
+        return Err(AppError::NotFound("No instances match given targets".to_string()));
+    }
+    let instance_ids: Vec<String> = instances.instances.iter()
+        .map(|instance| instance.instance_id.clone())
+        .collect();
+
+    // dry run has no side effects, so it does not need confirmation
+    if !args.yes && !args.dry_run {
+        let count = instance_ids.len();
+        let table: Table = instances.into();
+        table.render(2)?;
+
+        let question = format!("Do you want to {} {} instance(s)?", action, count);
+        let confirmed = confirm(&question).map_err(|e| AppError::GeneralError(e.to_string()))?;
+        if !confirmed {
+            return Err(AppError::GeneralError("Aborted by user".to_string()));
+        }
+    }
+
+    let changes = provider.change_state(&instance_ids, action, args.dry_run).await?;
+    outputs::render(changes, &cli.output())?;
+
+    if args.wait && !args.dry_run {
+        wait_for_state(provider, &instance_ids, action.target_state(), Duration::from_secs(args.timeout)).await?;
+    }
+    Ok(())
+}
+
+/// Poll instances until all of them reach the target state, drawing progress on stderr
+async fn wait_for_state<P: ProviderActions>(provider: &P, instance_ids: &[String], target_state: &str, timeout: Duration) -> Result<(), AppError> {
+    info!("Waiting for instances to be {}...", target_state);
+    let term = Term::stderr();
+    let query = InstanceQuery {
+        instance_ids: instance_ids.to_vec(),
+        ..Default::default()
+    };
+
+    let started = Instant::now();
+    let mut drawn_lines = 0;
+    loop {
+        let instances = provider.list_instances(&query).await?;
+        let done = instances.instances.iter()
+            .filter(|instance| instance.state == target_state)
+            .count();
+
+        if term.is_term() {
+            let mut lines = vec![format!(
+                "Waiting for instances to be {} ({}/{} done, {}s elapsed)",
+                target_state, done, instances.instances.len(), started.elapsed().as_secs(),
+            )];
+            lines.extend(instances.instances.iter().map(|instance| format!(
+                "  {} {:<24} {}",
+                instance.instance_id, instance.name, instance.state,
+            )));
+
+            term.clear_last_lines(drawn_lines).map_err(OutputError::from)?;
+            for line in &lines {
+                term.write_line(line).map_err(OutputError::from)?;
+            }
+            drawn_lines = lines.len();
+        } else {
+            debug!("{}/{} instances are {}", done, instances.instances.len(), target_state);
+        }
+
+        if done == instances.instances.len() {
+            info!("All instances are {}", target_state);
+            return Ok(());
+        }
+        if started.elapsed() >= timeout {
+            return Err(AppError::TimeoutError);
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
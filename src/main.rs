@@ -1,15 +1,40 @@
 mod app;
 use app::run_app;
 
+mod accounts;
 mod actions;
 mod cli;
+mod config;
+mod fanout;
+mod lifecycle;
+mod params;
+mod prompt;
 mod providers;
+mod run;
+mod session;
+mod tunnel;
 mod outputs;
 
+use clap::Parser;
+use cli::{CLI, ErrorFormat};
+use std::process::ExitCode;
+
 #[tokio::main]
-async fn main() {
-    match run_app().await {
-        Ok(_) => {}
-        Err(e) => eprintln!("Error: {}", e),
+async fn main() -> ExitCode {
+    let cli = CLI::parse();
+    let error_format = cli.error_format.clone();
+
+    match run_app(cli).await {
+        Ok(_) => ExitCode::SUCCESS,
+        Err(e) => {
+            match error_format {
+                ErrorFormat::Text => eprintln!("Error: {}", e),
+                ErrorFormat::Json => match serde_json::to_string(&e.report()) {
+                    Ok(report) => eprintln!("{}", report),
+                    Err(_) => eprintln!("Error: {}", e),
+                },
+            }
+            ExitCode::from(e.exit_code())
+        }
     }
 }
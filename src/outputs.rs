@@ -1 +1,78 @@
 pub mod table;
+pub mod json;
+pub mod yaml;
+pub mod delimited;
+
+use std::fmt;
+use std::io;
+use std::io::{IsTerminal, Write};
+use serde::Serialize;
+
+use crate::cli::OutputFormat;
+use crate::outputs::table::{Table, TableError};
+
+#[derive(Debug)]
+pub enum OutputError {
+    TableError(TableError),
+    SerializationError(String),
+    IOError(io::Error),
+}
+
+impl fmt::Display for OutputError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OutputError::TableError(err) => write!(f, "{}", err),
+            OutputError::SerializationError(msg) => write!(f, "Serialization error: {}", msg),
+            OutputError::IOError(err) => write!(f, "IO Error: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for OutputError {}
+
+impl From<TableError> for OutputError {
+    fn from(err: TableError) -> Self {
+        OutputError::TableError(err)
+    }
+}
+
+impl From<io::Error> for OutputError {
+    fn from(err: io::Error) -> Self {
+        OutputError::IOError(err)
+    }
+}
+
+/// Define data that can be rendered by every output format
+pub trait Renderable: Serialize + Into<Table> {
+    /// Type of a single row written by delimited (CSV/TSV) outputs
+    type Record: Serialize;
+
+    /// Flat list of records used by delimited (CSV/TSV) outputs
+    fn records(&self) -> &[Self::Record];
+}
+
+/// Render response data in the selected output format
+pub fn render<T: Renderable>(data: T, format: &OutputFormat) -> Result<(), OutputError> {
+    match format {
+        OutputFormat::Table => {
+            let table: Table = data.into();
+            table.render(2)?;
+        }
+        OutputFormat::Json => json::render(&data)?,
+        OutputFormat::Yaml => yaml::render(&data)?,
+        OutputFormat::Csv => delimited::render(data.records(), b',')?,
+        OutputFormat::Tsv => delimited::render(data.records(), b'\t')?,
+    }
+    Ok(())
+}
+
+/// Write bare value for use in scripts, terminated by newline only on a terminal
+pub fn render_raw(value: &str) -> Result<(), OutputError> {
+    let mut stdout = io::stdout().lock();
+    stdout.write_all(value.as_bytes())?;
+    if stdout.is_terminal() {
+        stdout.write_all(b"\n")?;
+    }
+    stdout.flush()?;
+    Ok(())
+}
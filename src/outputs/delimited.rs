@@ -0,0 +1,67 @@
+use std::io;
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::outputs::OutputError;
+
+/// Write records to stdout as delimiter-separated values with a header row
+///
+/// Nested objects (e.g. tags) are flattened into `parent.key` columns, header is
+/// an union of columns of every record, in order of their first appearance.
+pub fn render<T: Serialize>(records: &[T], delimiter: u8) -> Result<(), OutputError> {
+    let mut rows: Vec<Map<String, Value>> = Vec::new();
+    let mut header: Vec<String> = Vec::new();
+
+    for record in records {
+        let value = serde_json::to_value(record)
+            .map_err(|e| OutputError::SerializationError(e.to_string()))?;
+
+        let mut row = Map::new();
+        flatten("", value, &mut row);
+        for key in row.keys() {
+            if !header.contains(key) {
+                header.push(key.clone());
+            }
+        }
+        rows.push(row);
+    }
+
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(delimiter)
+        .from_writer(io::stdout());
+
+    let serialization_error = |e: csv::Error| OutputError::SerializationError(e.to_string());
+    writer.write_record(&header).map_err(serialization_error)?;
+    for row in rows {
+        let fields: Vec<String> = header.iter()
+            .map(|key| row.get(key).map(field).unwrap_or_default())
+            .collect();
+        writer.write_record(&fields).map_err(serialization_error)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Flatten nested objects into a single level map with dotted keys
+fn flatten(prefix: &str, value: Value, output: &mut Map<String, Value>) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                let key = if prefix.is_empty() { key } else { format!("{}.{}", prefix, key) };
+                flatten(&key, value, output);
+            }
+        }
+        other => {
+            output.insert(prefix.to_string(), other);
+        }
+    }
+}
+
+/// Convert single value into a field, empty for missing values
+fn field(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
@@ -0,0 +1,16 @@
+use std::io;
+use std::io::Write;
+use serde::Serialize;
+
+use crate::outputs::OutputError;
+
+/// Write data to stdout as pretty-printed JSON
+pub fn render<T: Serialize>(data: &T) -> Result<(), OutputError> {
+    let mut stdout = io::stdout();
+
+    serde_json::to_writer_pretty(&mut stdout, data)
+        .map_err(|e| OutputError::SerializationError(e.to_string()))?;
+    stdout.write_all(b"\n")?;
+    stdout.flush()?;
+    Ok(())
+}
@@ -3,7 +3,10 @@ use std::io::Write;
 use std::fmt;
 use console;
 
-use crate::providers::aws::{STSResponse, Ec2Response, SsmResponse};
+use crate::actions::{CommandInvocationList, Identity, InstanceColumn, InstanceDetails, InstanceList, ParameterChangeList, ParameterColumn, ParameterHistory, ParameterList, StateChangeList};
+use crate::config::ContextList;
+use crate::params::diff::ParameterDiffList;
+use crate::tunnel::TunnelList;
 
 pub struct Table {
     format: Vec<TableColumnFormat>,
@@ -41,29 +44,67 @@ impl From<std::io::Error> for TableError {
     }
 }
 
-impl From<Ec2Response> for Table {
-    fn from(response: Ec2Response) -> Self {
+impl From<InstanceList> for Table {
+    fn from(response: InstanceList) -> Self {
+        let columns = if response.columns.is_empty() {
+            InstanceColumn::defaults()
+        } else {
+            response.columns
+        };
+
+        let mut table = Table::new(
+            columns.iter().map(|column| column.header()).collect(),
+            columns.iter()
+                .map(|column| match column {
+                    InstanceColumn::Name => TableColumnFormat::ToRight,
+                    _ => TableColumnFormat::ToLeft,
+                })
+                .collect::<Vec<TableColumnFormat>>()
+                .into(),
+        );
+
+        for instance in response.instances {
+            let _ = table.push(columns.iter().map(|column| column.value(&instance)).collect());
+        }
+
+        table
+    }
+}
+
+impl From<ParameterList> for Table {
+    fn from(response: ParameterList) -> Self {
+        let columns = if response.columns.is_empty() {
+            ParameterColumn::defaults()
+        } else {
+            response.columns
+        };
+
+        let mut table = Table::new(columns.iter().map(|column| column.header()).collect(), None);
+
+        for parameter in response.parameters {
+            let _ = table.push(columns.iter().map(|column| column.value(&parameter)).collect());
+        }
+
+        table
+    }
+}
+
+impl From<ParameterChangeList> for Table {
+    fn from(response: ParameterChangeList) -> Self {
         let mut table = Table::new(
             vec![
                 "Name".to_string(),
-                "Instance ID".to_string(),
-                "State".to_string(),
-                "Private IP".to_string(),
+                "Action".to_string(),
+                "Version".to_string(),
             ],
-            vec![
-                TableColumnFormat::ToRight,
-                TableColumnFormat::ToLeft,
-                TableColumnFormat::ToLeft,
-                TableColumnFormat::ToLeft,
-            ].into(),
+            None,
         );
 
-        for instance in response.instances {
+        for change in response.changes {
             let _ = table.push(vec![
-                instance.name,
-                instance.instance_id,
-                instance.state,
-                instance.private_ip,
+                change.name,
+                change.action,
+                change.version.map(|version| version.to_string()).unwrap_or("-".to_string()),
             ]);
         }
 
@@ -71,32 +112,68 @@ impl From<Ec2Response> for Table {
     }
 }
 
-impl From<SsmResponse> for Table {
-    fn from(response: SsmResponse) -> Self {
+impl From<ParameterHistory> for Table {
+    fn from(response: ParameterHistory) -> Self {
         let mut table = Table::new(
             vec![
-                "Name".to_string(),
+                "Version".to_string(),
+                "Modified".to_string(),
+                "Modified By".to_string(),
+                "Labels".to_string(),
                 "Type".to_string(),
                 "Value".to_string(),
             ],
             None,
         );
 
-        for parameter in response.parameters {
+        for version in response.versions {
+            let labels = if version.labels.is_empty() { "-".to_string() } else { version.labels.join(",") };
             let _ = table.push(vec![
-                parameter.name,
-                parameter.r#type,
-                parameter.value,
+                version.version.to_string(),
+                version.last_modified.unwrap_or("-".to_string()),
+                version.modified_by.unwrap_or("-".to_string()),
+                labels,
+                version.r#type,
+                version.value,
             ]);
         }
 
         table
-
     }
+}
+
+impl From<ParameterDiffList> for Table {
+    fn from(response: ParameterDiffList) -> Self {
+        let mut table = Table::new(
+            vec![
+                "Name".to_string(),
+                "Status".to_string(),
+                response.left,
+                response.right,
+            ],
+            None,
+        );
+
+        for diff in response.differences {
+            let status = match diff.status.as_str() {
+                "added" => console::style(diff.status).green(),
+                "removed" => console::style(diff.status).red(),
+                _ => console::style(diff.status).yellow(),
+            };
+            let _ = table.push(vec![
+                diff.name,
+                status.to_string(),
+                diff.left.unwrap_or("-".to_string()),
+                diff.right.unwrap_or("-".to_string()),
+            ]);
+        }
 
+        table
+    }
 }
-impl From<STSResponse> for Table {
-    fn from(response: STSResponse) -> Self {
+
+impl From<Identity> for Table {
+    fn from(response: Identity) -> Self {
         let mut table: Table = Table::new(
             vec!["Param".to_string(), "Value".to_string()],
             None,
@@ -105,11 +182,221 @@ impl From<STSResponse> for Table {
         table.push(vec!["AWS ARN:".to_string(), response.arn]);
         table.push(vec!["User ID:".to_string(), response.user_id]);
         table.push(vec!["Account:".to_string(), response.account]);
+        table.push(vec!["Profile:".to_string(), response.profile.unwrap_or("<none>".to_string())]);
+        table.push(vec!["Region:".to_string(), response.region.unwrap_or("<none>".to_string())]);
 
         table
     }
 }
 
+impl From<InstanceDetails> for Table {
+    fn from(response: InstanceDetails) -> Self {
+        let mut table: Table = Table::new(
+            vec!["Param".to_string(), "Value".to_string()],
+            None,
+        ).set_header(false);
+
+        let instance = &response.instance;
+        for column in [
+            InstanceColumn::Name,
+            InstanceColumn::Id,
+            InstanceColumn::State,
+            InstanceColumn::Type,
+            InstanceColumn::Az,
+            InstanceColumn::Region,
+            InstanceColumn::PrivateIp,
+            InstanceColumn::PublicIp,
+            InstanceColumn::LaunchTime,
+            InstanceColumn::Ami,
+            InstanceColumn::KeyPair,
+            InstanceColumn::Vpc,
+            InstanceColumn::Subnet,
+            InstanceColumn::IamProfile,
+            InstanceColumn::Platform,
+        ] {
+            let _ = table.push(vec![format!("{}:", column.header()), column.value(instance)]);
+        }
+
+        let or_dash = |value: Option<String>| value.unwrap_or("-".to_string());
+        let _ = table.push(vec!["Monitoring:".to_string(), or_dash(response.monitoring)]);
+        let _ = table.push(vec!["System Status:".to_string(), or_dash(response.system_status)]);
+        let _ = table.push(vec!["Instance Status:".to_string(), or_dash(response.instance_status)]);
+
+        let _ = table.push(vec!["Network Interfaces:".to_string(), String::new()]);
+        for interface in response.network_interfaces {
+            let _ = table.push(vec![
+                format!("  {}", interface.interface_id),
+                format!(
+                    "{} public {} subnet {} groups {}",
+                    or_dash(interface.private_ip),
+                    or_dash(interface.public_ip),
+                    or_dash(interface.subnet_id),
+                    interface.security_groups.join(","),
+                ),
+            ]);
+        }
+
+        let _ = table.push(vec!["Security Groups:".to_string(), String::new()]);
+        for group in response.security_groups {
+            let _ = table.push(vec![format!("  {}", group.group_id), or_dash(group.group_name)]);
+        }
+
+        let _ = table.push(vec!["Block Devices:".to_string(), String::new()]);
+        for device in response.block_devices {
+            let delete = match device.delete_on_termination {
+                Some(true) => "deleted on termination",
+                Some(false) => "kept on termination",
+                None => "-",
+            };
+            let _ = table.push(vec![
+                format!("  {}", device.device_name),
+                format!("{} {} {}", or_dash(device.volume_id), or_dash(device.status), delete),
+            ]);
+        }
+
+        let _ = table.push(vec!["Tags:".to_string(), String::new()]);
+        for (key, value) in &instance.tags {
+            let _ = table.push(vec![format!("  {}", key), value.clone()]);
+        }
+
+        table
+    }
+}
+
+impl From<StateChangeList> for Table {
+    fn from(response: StateChangeList) -> Self {
+        let mut table = Table::new(
+            vec![
+                "Instance ID".to_string(),
+                "Previous State".to_string(),
+                "Current State".to_string(),
+            ],
+            None,
+        );
+
+        for change in response.changes {
+            let _ = table.push(vec![
+                change.instance_id,
+                change.previous_state.unwrap_or("-".to_string()),
+                change.current_state,
+            ]);
+        }
+
+        table
+    }
+}
+
+/// Longest command output shown in a table cell, longer output is truncated
+const OUTPUT_WIDTH: usize = 60;
+
+impl From<CommandInvocationList> for Table {
+    fn from(response: CommandInvocationList) -> Self {
+        let mut table = Table::new(
+            vec![
+                "Instance ID".to_string(),
+                "Name".to_string(),
+                "Status".to_string(),
+                "Exit Code".to_string(),
+                "Output".to_string(),
+            ],
+            None,
+        );
+
+        for invocation in response.invocations {
+            // failed commands usually explain themselves on stderr
+            let output = if invocation.stdout.trim().is_empty() { &invocation.stderr } else { &invocation.stdout };
+            let _ = table.push(vec![
+                invocation.instance_id,
+                invocation.name.unwrap_or("-".to_string()),
+                invocation.status,
+                invocation.exit_code.map(|code| code.to_string()).unwrap_or("-".to_string()),
+                truncate_output(output),
+            ]);
+        }
+
+        table
+    }
+}
+
+/// First non-empty line of command output, shortened to fit a table cell
+fn truncate_output(output: &str) -> String {
+    let line = output.lines()
+        .map(str::trim_end)
+        .find(|line| !line.is_empty())
+        .unwrap_or("-");
+    let more = output.trim().lines().count() > 1;
+
+    if line.chars().count() > OUTPUT_WIDTH {
+        format!("{}…", line.chars().take(OUTPUT_WIDTH - 1).collect::<String>())
+    } else if more {
+        format!("{} …", line)
+    } else {
+        line.to_string()
+    }
+}
+
+impl From<TunnelList> for Table {
+    fn from(response: TunnelList) -> Self {
+        let mut table = Table::new(
+            vec![
+                "Name".to_string(),
+                "Instance ID".to_string(),
+                "Local".to_string(),
+                "Remote".to_string(),
+                "Client".to_string(),
+                "PID".to_string(),
+                "Uptime".to_string(),
+            ],
+            None,
+        );
+
+        for tunnel in response.tunnels {
+            let uptime = tunnel.uptime();
+            let _ = table.push(vec![
+                tunnel.name,
+                tunnel.instance_id,
+                format!("localhost:{}", tunnel.local_port),
+                format!("{}:{}", tunnel.remote_host.unwrap_or("localhost".to_string()), tunnel.remote_port),
+                tunnel.client,
+                tunnel.pid.to_string(),
+                uptime,
+            ]);
+        }
+
+        table
+    }
+}
+
+impl From<ContextList> for Table {
+    fn from(response: ContextList) -> Self {
+        let mut table = Table::new(
+            vec![
+                "Current".to_string(),
+                "Name".to_string(),
+                "Provider".to_string(),
+                "Profile".to_string(),
+                "Region".to_string(),
+                "Output".to_string(),
+                "SSM Path".to_string(),
+            ],
+            None,
+        );
+
+        for context in response.contexts {
+            let _ = table.push(vec![
+                if context.current { "*".to_string() } else { String::new() },
+                context.name,
+                context.provider.unwrap_or("-".to_string()),
+                context.profile.unwrap_or("-".to_string()),
+                context.region.unwrap_or("-".to_string()),
+                context.output.unwrap_or("-".to_string()),
+                context.ssm_path.unwrap_or("-".to_string()),
+            ]);
+        }
+
+        table
+    }
+}
 
 impl Table {
     // FIXME should return result with error handling
@@ -0,0 +1,15 @@
+use std::io;
+use std::io::Write;
+use serde::Serialize;
+
+use crate::outputs::OutputError;
+
+/// Write data to stdout as YAML document
+pub fn render<T: Serialize>(data: &T) -> Result<(), OutputError> {
+    let mut stdout = io::stdout();
+
+    serde_yaml::to_writer(&mut stdout, data)
+        .map_err(|e| OutputError::SerializationError(e.to_string()))?;
+    stdout.flush()?;
+    Ok(())
+}
@@ -0,0 +1,238 @@
+pub mod diff;
+pub mod export;
+pub mod import;
+
+use crate::actions::{Parameter, ParameterChange, ParameterChangeList, ParameterDefinition, ParameterList, ProviderActions, ProviderError};
+use crate::app::AppError;
+use crate::cli::{CLI, ExportFormat, ParamDiffArgs};
+use crate::outputs;
+use crate::outputs::table::Table;
+use crate::prompt::confirm;
+use diff::ParameterDiffList;
+use import::PlannedChange;
+use log::{info, debug};
+
+use std::path::Path;
+
+/// Show a single parameter, or just its value when raw output is requested
+pub async fn execute_get<P: ProviderActions>(provider: &P, selector: &str, decrypt: bool, raw: bool, cli: &CLI) -> Result<(), AppError> {
+    let parameter = provider.get_parameter(selector, decrypt).await?;
+    if !raw {
+        outputs::render(ParameterList::from_iter([parameter]), &cli.output())?;
+        return Ok(());
+    }
+
+    // placeholder would end up in scripts as if it was the value
+    if parameter.r#type == "SecureString" && !decrypt {
+        return Err(AppError::GeneralError(format!("Parameter {} is encrypted", parameter.name))
+            .with_hint("pass --decrypt to print its value".to_string()));
+    }
+    outputs::render_raw(&parameter.value)?;
+    Ok(())
+}
+
+/// Create or update a single parameter
+pub async fn execute_put<P: ProviderActions>(provider: &P, definition: &ParameterDefinition, overwrite: bool, cli: &CLI) -> Result<(), AppError> {
+    let change = put(provider, definition, overwrite).await?;
+    outputs::render(ParameterChangeList { changes: vec![change] }, &cli.output())?;
+    Ok(())
+}
+
+/// Confirm and delete parameters, failing when some of them do not exist
+pub async fn execute_delete<P: ProviderActions>(provider: &P, names: &[String], yes: bool, cli: &CLI) -> Result<(), AppError> {
+    if !yes {
+        for name in names {
+            eprintln!("  {}", name);
+        }
+        let question = format!("Do you want to delete {} parameter(s)?", names.len());
+        let confirmed = confirm(&question).map_err(|e| AppError::GeneralError(e.to_string()))?;
+        if !confirmed {
+            return Err(AppError::GeneralError("Aborted by user".to_string()));
+        }
+    }
+
+    let changes = provider.delete_parameters(names).await?;
+    let missing = changes.changes.iter().filter(|change| change.action == "not found").count();
+    outputs::render(changes, &cli.output())?;
+
+    if missing > 0 {
+        return Err(AppError::NotFound(format!("{} parameter(s) do not exist", missing)));
+    }
+    Ok(())
+}
+
+/// Copy parameter with all its attributes under a new name, then delete the original
+pub async fn execute_move<P: ProviderActions>(provider: &P, source: &str, destination: &str, overwrite: bool, cli: &CLI) -> Result<(), AppError> {
+    if source == destination {
+        return Err(AppError::GeneralError("Source and destination are the same parameter".to_string()));
+    }
+
+    let definition = provider.describe_parameter(source).await?;
+    debug!("Copying {} parameter {} with {} tag(s)", definition.r#type, source, definition.tags.len());
+    let copy = ParameterDefinition {
+        name: destination.to_string(),
+        ..definition
+    };
+
+    // source is removed only once its copy exists
+    let created = put(provider, &copy, overwrite).await?;
+    info!("Parameter {} copied to {}, deleting the original...", source, destination);
+    let mut changes = provider.delete_parameters(&[source.to_string()]).await?;
+
+    changes.changes.insert(0, created);
+    outputs::render(changes, &cli.output())?;
+    Ok(())
+}
+
+/// Write decrypted parameters under the path as environment variables
+pub async fn execute_export<P: ProviderActions>(provider: &P, path: &str, format: &ExportFormat, secret_name: Option<&str>) -> Result<(), AppError> {
+    let parameters = provider.list_parameters(Some(path.to_string()), true, false).await?;
+    let variables = export::variables(&parameters.parameters, path)?;
+    debug!("Exporting {} variable(s) from {}", variables.len(), path);
+
+    let secret_name = secret_name.map(|name| name.to_string()).unwrap_or(export::secret_name(path));
+    export::render(&variables, format, &secret_name)?;
+    Ok(())
+}
+
+/// Plan changes needed to bring parameters under the prefix in line with the file, then apply them
+pub async fn execute_import<P: ProviderActions>(provider: &P, file: &Path, prefix: &str, secure_keys: &[String], dry_run: bool, yes: bool, cli: &CLI) -> Result<(), AppError> {
+    let variables = import::read_variables(file)?;
+    debug!("Read {} variable(s) from {}", variables.len(), file.display());
+
+    let existing = match provider.list_parameters(Some(prefix.to_string()), true, false).await {
+        Ok(parameters) => parameters.parameters,
+        // importing into an empty path seeds a new environment
+        Err(ProviderError::ResourceNotFound(_)) => Vec::new(),
+        Err(e) => return Err(e.into()),
+    };
+    let plan = import::plan(&variables, &existing, prefix, secure_keys);
+    let summary = ParameterChangeList { changes: plan.iter().map(PlannedChange::summary).collect() };
+
+    if dry_run {
+        outputs::render(summary, &cli.output())?;
+        return Ok(());
+    }
+
+    let pending: Vec<&PlannedChange> = plan.iter()
+        .filter(|change| change.action != "unchanged")
+        .collect();
+    if pending.is_empty() {
+        info!("All {} parameter(s) under {} are up to date", plan.len(), prefix);
+        return Ok(());
+    }
+
+    if !yes {
+        let table: Table = summary.into();
+        table.render(2)?;
+
+        let question = format!("Do you want to write {} parameter(s)?", pending.len());
+        let confirmed = confirm(&question).map_err(|e| AppError::GeneralError(e.to_string()))?;
+        if !confirmed {
+            return Err(AppError::GeneralError("Aborted by user".to_string()));
+        }
+    }
+
+    let mut changes = Vec::new();
+    for change in pending {
+        changes.push(put(provider, &change.definition, change.action == "update").await?);
+    }
+    outputs::render(ParameterChangeList { changes }, &cli.output())?;
+    Ok(())
+}
+
+/// Compare parameters under two paths, possibly read with different profiles or in different regions
+pub async fn execute_diff<P: ProviderActions>(provider: &P, args: &ParamDiffArgs, cli: &CLI) -> Result<(), AppError> {
+    let (left, right) = tokio::try_join!(
+        read_side(provider, &args.left, args.left_profile.as_deref(), args.left_region.as_deref()),
+        read_side(provider, &args.right, args.right_profile.as_deref(), args.right_region.as_deref()),
+    )?;
+
+    let differences = diff::compare(&left, &args.left, &right, &args.right, args.decrypt);
+    let count = differences.len();
+    outputs::render(ParameterDiffList {
+        left: describe_side(&args.left, args.left_profile.as_deref(), args.left_region.as_deref()),
+        right: describe_side(&args.right, args.right_profile.as_deref(), args.right_region.as_deref()),
+        differences,
+    }, &cli.output())?;
+
+    if count > 0 {
+        return Err(AppError::GeneralError(format!("{} parameter(s) differ between {} and {}", count, args.left, args.right)));
+    }
+    info!("Parameters under {} and {} are the same", args.left, args.right);
+    Ok(())
+}
+
+/// Decrypted parameters under the path, empty path is not an error
+async fn read_side<P: ProviderActions>(provider: &P, path: &str, profile: Option<&str>, region: Option<&str>) -> Result<Vec<Parameter>, AppError> {
+    let switched = match profile {
+        Some(profile) => Some(provider.with_profile(profile).await),
+        None => None,
+    };
+    let provider = switched.as_ref().unwrap_or(provider);
+    let regional = region.map(|region| provider.with_region(region));
+    let provider = regional.as_ref().unwrap_or(provider);
+
+    match provider.list_parameters(Some(path.to_string()), true, false).await {
+        Ok(parameters) => Ok(parameters.parameters),
+        Err(ProviderError::ResourceNotFound(_)) => Ok(Vec::new()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Path followed by profile and region it was read with, if given (`/app/prod (prod, eu-west-1)`)
+fn describe_side(path: &str, profile: Option<&str>, region: Option<&str>) -> String {
+    let details: Vec<&str> = profile.into_iter().chain(region).collect();
+    if details.is_empty() {
+        path.to_string()
+    } else {
+        format!("{} ({})", path, details.join(", "))
+    }
+}
+
+/// Show every version of a parameter
+pub async fn execute_history<P: ProviderActions>(provider: &P, name: &str, decrypt: bool, cli: &CLI) -> Result<(), AppError> {
+    let history = provider.get_parameter_history(name, decrypt).await?;
+    outputs::render(history, &cli.output())?;
+    Ok(())
+}
+
+/// Put value, type and KMS key of an earlier version as the newest version
+pub async fn execute_rollback<P: ProviderActions>(provider: &P, name: &str, version: i64, cli: &CLI) -> Result<(), AppError> {
+    let history = provider.get_parameter_history(name, true).await?;
+    let target = history.versions.iter()
+        .find(|v| v.version == version)
+        .ok_or_else(|| AppError::NotFound(format!("Parameter {} has no version {}", name, version)))?;
+
+    if let Some(current) = history.versions.last()
+        && current.value == target.value
+        && current.r#type == target.r#type
+    {
+        info!("Version {} of {} already has the value of version {}", current.version, name, version);
+        return Ok(());
+    }
+
+    debug!("Restoring {} to the value of version {}", name, version);
+    let definition = ParameterDefinition {
+        name: name.to_string(),
+        r#type: target.r#type.clone(),
+        value: target.value.clone(),
+        kms_key_id: target.kms_key_id.clone(),
+        description: target.description.clone(),
+        tags: Vec::new(),
+    };
+    let change = provider.put_parameter(&definition, true).await?;
+    outputs::render(ParameterChangeList { changes: vec![change] }, &cli.output())?;
+    Ok(())
+}
+
+/// Put parameter, pointing to `--overwrite` when it already exists
+async fn put<P: ProviderActions>(provider: &P, definition: &ParameterDefinition, overwrite: bool) -> Result<ParameterChange, AppError> {
+    match provider.put_parameter(definition, overwrite).await {
+        Err(ProviderError::ServiceError { code, .. }) if code == "ParameterAlreadyExists" => {
+            Err(AppError::GeneralError(format!("Parameter {} already exists", definition.name))
+                .with_hint("pass --overwrite to replace it".to_string()))
+        }
+        result => Ok(result?),
+    }
+}
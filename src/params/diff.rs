@@ -0,0 +1,78 @@
+use crate::actions::Parameter;
+use crate::outputs::Renderable;
+use serde::{Deserialize, Serialize};
+
+use std::collections::BTreeMap;
+
+/// Difference of a single parameter between two paths
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ParameterDiff {
+    /// Name relative to the compared paths
+    pub name: String,
+    /// `added` (right only), `removed` (left only) or `changed`
+    pub status: String,
+    pub left: Option<String>,
+    pub right: Option<String>,
+}
+
+/// Differences between two parameter paths
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ParameterDiffList {
+    /// Description of the left side, path optionally followed by profile and region
+    pub left: String,
+    /// Description of the right side
+    pub right: String,
+    pub differences: Vec<ParameterDiff>,
+}
+
+impl Renderable for ParameterDiffList {
+    type Record = ParameterDiff;
+
+    fn records(&self) -> &[ParameterDiff] {
+        &self.differences
+    }
+}
+
+/// Align parameters of both paths by relative name and list those which differ
+///
+/// Values are compared in full, secure ones are masked in the result unless revealing is requested.
+pub fn compare(left: &[Parameter], left_path: &str, right: &[Parameter], right_path: &str, reveal: bool) -> Vec<ParameterDiff> {
+    let left = relative(left, left_path);
+    let right = relative(right, right_path);
+    let shown = |param: &Parameter| {
+        if param.r#type == "SecureString" && !reveal {
+            "<encrypted>".to_string()
+        } else {
+            param.value.clone()
+        }
+    };
+
+    let mut names: Vec<&str> = left.keys().chain(right.keys()).copied().collect();
+    names.sort();
+    names.dedup();
+
+    names.into_iter()
+        .filter_map(|name| {
+            let status = match (left.get(name), right.get(name)) {
+                (Some(_), None) => "removed",
+                (None, Some(_)) => "added",
+                (Some(l), Some(r)) if l.value != r.value || l.r#type != r.r#type => "changed",
+                _ => return None,
+            };
+            Some(ParameterDiff {
+                name: name.to_string(),
+                status: status.to_string(),
+                left: left.get(name).map(|param| shown(param)),
+                right: right.get(name).map(|param| shown(param)),
+            })
+        })
+        .collect()
+}
+
+/// Parameters under the path, by name relative to it
+fn relative<'a>(parameters: &'a [Parameter], path: &str) -> BTreeMap<&'a str, &'a Parameter> {
+    let prefix = format!("{}/", path.trim_end_matches('/'));
+    parameters.iter()
+        .filter_map(|param| Some((param.name.strip_prefix(&prefix)?, param)))
+        .collect()
+}
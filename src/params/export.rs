@@ -0,0 +1,121 @@
+use crate::actions::Parameter;
+use crate::app::AppError;
+use crate::cli::ExportFormat;
+use crate::outputs::{OutputError, json, yaml};
+use serde::Serialize;
+
+use std::collections::BTreeMap;
+use std::io;
+use std::io::Write;
+
+/// Kubernetes Secret holding exported variables as plain string data
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SecretManifest<'a> {
+    api_version: &'static str,
+    kind: &'static str,
+    metadata: SecretMetadata,
+    r#type: &'static str,
+    string_data: &'a BTreeMap<String, String>,
+}
+
+#[derive(Debug, Serialize)]
+struct SecretMetadata {
+    name: String,
+}
+
+/// Environment variable name of a parameter, relative to the exported path (`db/url` -> `DB_URL`)
+pub fn env_key(relative_name: &str) -> String {
+    let mut key: String = relative_name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+        .collect();
+    if key.starts_with(|c: char| c.is_ascii_digit()) {
+        key.insert(0, '_');
+    }
+    key
+}
+
+/// Map parameters under the path to environment variables, failing on names mapped to the same key
+pub fn variables(parameters: &[Parameter], path: &str) -> Result<BTreeMap<String, String>, AppError> {
+    let prefix = format!("{}/", path.trim_end_matches('/'));
+    let mut sources: BTreeMap<String, Vec<&Parameter>> = BTreeMap::new();
+    for parameter in parameters {
+        // provider may return siblings sharing the prefix, e.g. `/app/production` for `/app/prod`
+        if let Some(relative_name) = parameter.name.strip_prefix(&prefix) {
+            sources.entry(env_key(relative_name)).or_default().push(parameter);
+        }
+    }
+
+    let collisions: Vec<String> = sources.iter()
+        .filter(|(_, parameters)| parameters.len() > 1)
+        .map(|(key, parameters)| format!(
+            "{} <- {}",
+            key, parameters.iter().map(|p| p.name.as_str()).collect::<Vec<_>>().join(", "),
+        ))
+        .collect();
+    if !collisions.is_empty() {
+        return Err(AppError::GeneralError(format!("Several parameters map to the same variable: {}", collisions.join("; ")))
+            .with_hint("rename the parameters so that their names differ in letters or digits".to_string()));
+    }
+
+    Ok(sources.into_iter()
+        .map(|(key, mut parameters)| (key, parameters.remove(0).value.clone()))
+        .collect())
+}
+
+/// Write variables to stdout in the selected format
+pub fn render(variables: &BTreeMap<String, String>, format: &ExportFormat, secret_name: &str) -> Result<(), OutputError> {
+    match format {
+        ExportFormat::Dotenv => write_lines(variables.iter().map(|(key, value)| format!("{}={}", key, dotenv_value(value)))),
+        ExportFormat::Sh => write_lines(variables.iter().map(|(key, value)| format!("export {}={}", key, shell_quote(value)))),
+        ExportFormat::Json => json::render(variables),
+        ExportFormat::K8sSecret => yaml::render(&SecretManifest {
+            api_version: "v1",
+            kind: "Secret",
+            metadata: SecretMetadata { name: secret_name.to_string() },
+            r#type: "Opaque",
+            string_data: variables,
+        }),
+    }
+}
+
+/// Default Secret name derived from the exported path (`/app/prod` -> `app-prod`)
+pub fn secret_name(path: &str) -> String {
+    let name: String = path.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '-' })
+        .collect();
+    name.split('-')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+fn write_lines(lines: impl Iterator<Item = String>) -> Result<(), OutputError> {
+    let mut stdout = io::stdout();
+    for line in lines {
+        stdout.write_all(line.as_bytes())?;
+        stdout.write_all(b"\n")?;
+    }
+    stdout.flush()?;
+    Ok(())
+}
+
+/// Value as understood by dotenv parsers, quoted only when needed
+fn dotenv_value(value: &str) -> String {
+    let plain = value.chars().all(|c| c.is_ascii_alphanumeric() || "_-./:@,+".contains(c));
+    if plain {
+        return value.to_string();
+    }
+
+    let escaped = value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+        .replace('$', "\\$");
+    format!("\"{}\"", escaped)
+}
+
+/// Value single-quoted for POSIX shells
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
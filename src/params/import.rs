@@ -0,0 +1,163 @@
+use crate::actions::{Parameter, ParameterChange, ParameterDefinition, glob_match};
+use crate::app::AppError;
+use crate::params::export::env_key;
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// Single step of an import, together with the parameter it writes
+#[derive(Debug)]
+pub struct PlannedChange {
+    pub definition: ParameterDefinition,
+    /// `create`, `update` or `unchanged`
+    pub action: &'static str,
+}
+
+impl PlannedChange {
+    pub fn summary(&self) -> ParameterChange {
+        ParameterChange {
+            name: self.definition.name.clone(),
+            action: self.action.to_string(),
+            version: None,
+        }
+    }
+}
+
+/// Read variables from dotenv, JSON or YAML file, chosen by file extension
+pub fn read_variables(path: &Path) -> Result<BTreeMap<String, String>, AppError> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| AppError::GeneralError(format!("Cannot read {}: {}", path.display(), e)))?;
+    let invalid = |msg: String| AppError::GeneralError(format!("Invalid file {}: {}", path.display(), msg));
+
+    let extension = path.extension().and_then(|ext| ext.to_str()).unwrap_or("");
+    let variables = match extension {
+        "json" => {
+            let data: BTreeMap<String, serde_json::Value> = serde_json::from_str(&content).map_err(|e| invalid(e.to_string()))?;
+            data.into_iter()
+                .map(|(key, value)| match value {
+                    serde_json::Value::String(value) => Ok((key, value)),
+                    serde_json::Value::Number(_) | serde_json::Value::Bool(_) => Ok((key, value.to_string())),
+                    _ => Err(invalid(format!("value of {} is not a string, number or boolean", key))),
+                })
+                .collect::<Result<_, _>>()?
+        }
+        "yaml" | "yml" => {
+            let data: BTreeMap<String, serde_yaml::Value> = serde_yaml::from_str(&content).map_err(|e| invalid(e.to_string()))?;
+            data.into_iter()
+                .map(|(key, value)| match value {
+                    serde_yaml::Value::String(value) => Ok((key, value)),
+                    serde_yaml::Value::Number(number) => Ok((key, number.to_string())),
+                    serde_yaml::Value::Bool(flag) => Ok((key, flag.to_string())),
+                    _ => Err(invalid(format!("value of {} is not a string, number or boolean", key))),
+                })
+                .collect::<Result<_, _>>()?
+        }
+        _ => parse_dotenv(&content).map_err(invalid)?,
+    };
+
+    if let Some(key) = variables.keys().find(|key| key.is_empty() || key.contains(['/', '=']) || key.contains(char::is_whitespace)) {
+        return Err(invalid(format!("'{}' is not a valid variable name", key)));
+    }
+
+    // variables differing only in case or punctuation would write the same parameter
+    let mut keys: BTreeMap<String, &str> = BTreeMap::new();
+    for key in variables.keys() {
+        if let Some(other) = keys.insert(env_key(key), key) {
+            return Err(invalid(format!("variables {} and {} map to the same parameter", other, key)));
+        }
+    }
+    Ok(variables)
+}
+
+/// Compare variables with parameters already stored under the prefix
+///
+/// Variables are matched to existing parameters the same way `params export` names them,
+/// so exported files are imported back onto the same parameters. Variables without
+/// a match create parameters named after the lowercased variable.
+pub fn plan(variables: &BTreeMap<String, String>, existing: &[Parameter], prefix: &str, secure_keys: &[String]) -> Vec<PlannedChange> {
+    let prefix = prefix.trim_end_matches('/');
+    let existing: BTreeMap<String, &Parameter> = existing.iter()
+        .filter_map(|param| {
+            let relative_name = param.name.strip_prefix(prefix)?.strip_prefix('/')?;
+            Some((env_key(relative_name), param))
+        })
+        .collect();
+
+    variables.iter()
+        .map(|(key, value)| {
+            let current = existing.get(&env_key(key));
+            let secure = secure_keys.iter().any(|pattern| glob_match(&pattern.to_uppercase(), &key.to_uppercase()))
+                // secure parameter is never turned into plain text by an import
+                || current.is_some_and(|param| param.r#type == "SecureString");
+            let r#type = if secure { "SecureString" } else { "String" };
+
+            let action = match current {
+                None => "create",
+                Some(param) if param.value == *value && param.r#type == r#type => "unchanged",
+                Some(_) => "update",
+            };
+            PlannedChange {
+                definition: ParameterDefinition {
+                    name: current.map(|param| param.name.clone()).unwrap_or(format!("{}/{}", prefix, key.to_lowercase())),
+                    r#type: r#type.to_string(),
+                    value: value.clone(),
+                    kms_key_id: None,
+                    description: None,
+                    tags: Vec::new(),
+                },
+                action,
+            }
+        })
+        .collect()
+}
+
+/// Parse `KEY=value` lines, accepting `export` prefix, comments and quoted values
+fn parse_dotenv(content: &str) -> Result<BTreeMap<String, String>, String> {
+    let mut variables = BTreeMap::new();
+    for (number, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let (key, value) = line.split_once('=')
+            .ok_or_else(|| format!("line {}: expected KEY=value", number + 1))?;
+        let value = value.trim();
+
+        let value = if let Some(quoted) = value.strip_prefix('"') {
+            let quoted = quoted.strip_suffix('"')
+                .ok_or_else(|| format!("line {}: unterminated quoted value", number + 1))?;
+            unescape(quoted)
+        } else if let Some(quoted) = value.strip_prefix('\'') {
+            quoted.strip_suffix('\'')
+                .ok_or_else(|| format!("line {}: unterminated quoted value", number + 1))?
+                .to_string()
+        } else {
+            // unquoted values may be followed by a comment
+            value.split(" #").next().unwrap_or("").trim_end().to_string()
+        };
+        variables.insert(key.trim().to_string(), value);
+    }
+    Ok(variables)
+}
+
+/// Resolve backslash escapes of a double-quoted dotenv value
+fn unescape(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => result.push('\n'),
+            Some('t') => result.push('\t'),
+            Some(other) => result.push(other),
+            None => result.push('\\'),
+        }
+    }
+    result
+}
@@ -0,0 +1,21 @@
+use console::Term;
+use log::debug;
+
+use std::io;
+use std::io::IsTerminal;
+
+/// Ask user a yes/no question on the terminal, defaulting to "no"
+///
+/// Returns an error when stdin is not a terminal, so that scripts have to pass `--yes` explicitly.
+pub fn confirm(question: &str) -> io::Result<bool> {
+    let term = Term::stderr();
+    if !term.is_term() || !io::stdin().is_terminal() {
+        return Err(io::Error::other("confirmation required, but terminal is not interactive (use --yes)"));
+    }
+
+    term.write_str(&format!("{} [y/N] ", question))?;
+    let answer = term.read_line()?;
+    debug!("User answered: {}", answer);
+
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
@@ -1,240 +1,845 @@
-use crate::actions::ProviderError;
-use aws_config::{load_defaults,BehaviorVersion};
+use crate::actions::{ProviderActions, ProviderError, Identity, Instance, InstanceList, InstanceQuery, Parameter, ParameterList};
+use crate::actions::{BlockDevice, InstanceDetails, NetworkInterface, SecurityGroup, single_instance};
+use crate::actions::{InstanceAction, StateChange, StateChangeList};
+use crate::actions::{Session, SessionRequest};
+use crate::actions::CommandInvocation;
+use crate::actions::{ParameterChange, ParameterChangeList, ParameterDefinition, ParameterHistory, ParameterVersion};
+use aws_config::{BehaviorVersion, Region, SdkConfig};
+use aws_config::sts::AssumeRoleProvider;
+use aws_credential_types::provider::{ProvideCredentials, SharedCredentialsProvider};
 use log::{info, debug, error};
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+
+mod errors;
+use errors::{map_credentials_error, map_sdk_error};
 
 use aws_sdk_sts::Client as STSClient;
 use aws_sdk_ec2::Client as EC2Client;
+use aws_sdk_ec2::primitives::DateTimeFormat;
+use aws_sdk_ec2::types::{Filter, Instance as Ec2Instance, InstanceStateChange};
 use aws_sdk_ssm::Client as SSMClient;
-use aws_sdk_ssm::error::SdkError;
-use aws_sdk_ssm::types::ParameterType;
+use aws_sdk_ssm::error::{ProvideErrorMetadata, SdkError};
+use aws_sdk_ssm::types::{Parameter as SsmParameter, ParameterStringFilter, ParameterType, ResourceTypeForTagging, Tag};
 
-#[derive(Debug)]
-pub struct AwsProvider {}
+/// Document running shell scripts on Linux instances
+const RUN_SHELL_SCRIPT_DOCUMENT: &str = "AWS-RunShellScript";
 
-#[derive(Debug)]
-pub struct STSResponse {
-   pub account: String,
-   pub arn: String,
-   pub user_id: String
-}
+/// Maximum number of instances targeted by a single SendCommand request
+const SEND_COMMAND_BATCH_SIZE: usize = 50;
 
-#[derive(Debug)]
-pub struct Ec2Instance {
-    pub name: String,
-    pub instance_id: String,
-    pub state: String,
-    pub private_ip: String,
-}
+/// Service error codes returned when parameter, its version or label does not exist
+const PARAMETER_NOT_FOUND_CODES: [&str; 3] = ["ParameterNotFound", "ParameterVersionNotFound", "ParameterVersionLabelNotFound"];
+
+/// Maximum number of parameters removed by a single DeleteParameters request
+const DELETE_PARAMETERS_BATCH_SIZE: usize = 10;
 
 #[derive(Debug)]
-pub struct Ec2Response {
-    pub instances: Vec<Ec2Instance>,
+pub struct AwsProvider {
+    config: SdkConfig,
+    profile: Option<String>,
+    sts_client: OnceLock<STSClient>,
+    ec2_client: OnceLock<EC2Client>,
+    ssm_client: OnceLock<SSMClient>,
 }
 
-impl Ec2Response {
-    pub fn new() -> Self {
-        Ec2Response {
-            instances: Vec::new(),
+impl AwsProvider {
+    /// Load shared SDK configuration, optionally overriding profile and region
+    pub async fn new(profile: Option<String>, region: Option<String>) -> Self {
+        debug!("Loading AWS configuration...");
+        let mut loader = aws_config::defaults(BehaviorVersion::latest());
+        if let Some(profile) = &profile {
+            debug!("Using AWS profile {}", profile);
+            loader = loader.profile_name(profile);
+        }
+        if let Some(region) = region {
+            debug!("Using AWS region {}", region);
+            loader = loader.region(Region::new(region));
         }
-    }
 
-    pub fn push(&mut self, instance: Ec2Instance) {
-        self.instances.push(instance);
-    }
-}
+        // resolve profile the same way SDK does, for displaying purposes
+        let profile = profile
+            .or_else(|| std::env::var("AWS_PROFILE").ok())
+            .or(Some("default".to_string()));
 
-impl FromIterator<Ec2Instance> for Ec2Response {
-    fn from_iter<I: IntoIterator<Item = Ec2Instance>>(iter: I) -> Self {
-        let instances: Vec<Ec2Instance> = iter.into_iter().collect();
-        Ec2Response { instances }
+        Self::from_config(loader.load().await, profile)
     }
 
-}
+    fn from_config(config: SdkConfig, profile: Option<String>) -> Self {
+        AwsProvider {
+            config,
+            profile,
+            sts_client: OnceLock::new(),
+            ec2_client: OnceLock::new(),
+            ssm_client: OnceLock::new(),
+        }
+    }
 
-#[derive(Debug)]
-pub struct SsmParameter {
-    pub name: String,
-    pub r#type: String,
-    pub value: String,
-}
+    /// Name of the region this provider is bound to
+    fn region(&self) -> Option<String> {
+        self.config.region().map(|r| r.to_string())
+    }
 
-#[derive(Debug)]
-pub struct SsmResponse {
-    pub parameters: Vec<SsmParameter>,
-}
+    /// Convert SDK error of given operation into provider error
+    fn sdk_error<E, R>(&self, err: SdkError<E, R>, operation: &str) -> ProviderError
+    where
+        E: ProvideErrorMetadata + std::error::Error + 'static,
+        R: std::fmt::Debug + 'static,
+    {
+        map_sdk_error(err, operation, &self.profile, &self.region())
+    }
 
-impl SsmResponse {
-    pub fn new() -> Self {
-        SsmResponse {
-            parameters: Vec::new(),
+    /// Convert error of state changing operation, `None` marks successful dry run
+    fn state_change_error<E, R>(&self, err: SdkError<E, R>, operation: &str) -> Option<ProviderError>
+    where
+        E: ProvideErrorMetadata + std::error::Error + 'static,
+        R: std::fmt::Debug + 'static,
+    {
+        if err.as_service_error().and_then(|e| e.code()) == Some("DryRunOperation") {
+            debug!("Dry run of {} succeeded", operation);
+            return None;
         }
+        Some(self.sdk_error(err, operation))
     }
 
-    pub fn push(&mut self, parameter: SsmParameter) {
-        self.parameters.push(parameter);
+    fn sts(&self) -> &STSClient {
+        self.sts_client.get_or_init(|| {
+            debug!("Creating STS client...");
+            STSClient::new(&self.config)
+        })
     }
-}
 
-impl FromIterator<SsmParameter> for SsmResponse {
-    fn from_iter<I: IntoIterator<Item = SsmParameter>>(iter: I) -> Self {
-        let parameters: Vec<SsmParameter> = iter.into_iter().collect();
-        SsmResponse { parameters }
+    fn ec2(&self) -> &EC2Client {
+        self.ec2_client.get_or_init(|| {
+            debug!("Creating EC2 client...");
+            EC2Client::new(&self.config)
+        })
     }
-}
 
+    fn ssm(&self) -> &SSMClient {
+        self.ssm_client.get_or_init(|| {
+            debug!("Creating SSM client...");
+            SSMClient::new(&self.config)
+        })
+    }
 
-impl AwsProvider {
-    pub fn new() -> Self {
-        AwsProvider {}
+    /// Fill metadata which GetParametersByPath does not return, from DescribeParameters under the same path
+    async fn add_parameter_metadata(&self, path: &str, parameters: &mut [Parameter]) -> Result<(), ProviderError> {
+        debug!("Obtaining metadata of parameters under {}", path);
+        let filter = ParameterStringFilter::builder()
+            .key("Path")
+            .option("Recursive")
+            .values(path)
+            .build()
+            .map_err(|e| ProviderError::GeneralError(e.to_string()))?;
+        let mut pages = self.ssm().describe_parameters()
+            .parameter_filters(filter)
+            .into_paginator()
+            .send();
+
+        let mut metadata = BTreeMap::new();
+        while let Some(page) = pages.next().await {
+            let page = page.map_err(|e| self.sdk_error(e, "DescribeParameters"))?;
+            for item in page.parameters() {
+                if let Some(name) = item.name() {
+                    metadata.insert(name.to_string(), item.clone());
+                }
+            }
+        }
+
+        for parameter in parameters.iter_mut() {
+            let Some(item) = metadata.get(&parameter.name) else {
+                debug!("No metadata returned for parameter {}", parameter.name);
+                continue;
+            };
+            parameter.modified_by = item.last_modified_user().map(|user| user.to_string());
+            parameter.tier = item.tier().map(|tier| tier.as_str().to_string());
+            parameter.kms_key_id = item.key_id().map(|key| key.to_string());
+            parameter.description = item.description().map(|description| description.to_string());
+        }
+        Ok(())
     }
+}
 
-    pub async fn who_am_i(&self) -> Result<STSResponse, ProviderError> {
+impl ProviderActions for AwsProvider {
+    async fn who_am_i(&self) -> Result<Identity, ProviderError> {
         info!("Fetching AWS identity...");
 
-        // Create AWS SDK client
-        let config = aws_config::load_from_env().await;
-        let client = STSClient::new(&config);
-
         //execute get-caller-identity method
-        let response = client.get_caller_identity()
+        let response = self.sts().get_caller_identity()
             .send()
             .await
-            .map_err(|e| {
-            error!("Failed to get caller identity: {}", e);
-            ProviderError::AuthenticationError
-        })?;
+            .map_err(|e| self.sdk_error(e, "GetCallerIdentity"))?;
 
-        Ok(STSResponse {
+        Ok(Identity {
             account: response.account().unwrap_or("<unknown>").to_string(),
             arn: response.arn().unwrap_or("<unknown>").to_string(),
             user_id: response.user_id().unwrap_or("<unknkown>").to_string(),
+            profile: self.profile.clone(),
+            region: self.region(),
         })
     }
 
-    pub async fn list_instances(&self) -> Result<Ec2Response, ProviderError> {
+    async fn list_instances(&self, query: &InstanceQuery) -> Result<InstanceList, ProviderError> {
         info!("Listing AWS instances...");
 
-        debug!("Creating EC2 client...");
-        let config = aws_config::load_from_env().await;
-        let ec2_client = EC2Client::new(&config);
-
         debug!("Obtaining data about EC2 instances...");
-        let response = ec2_client.describe_instances()
-            .send()
-            .await
-            .map_err(|e| {
-                error!("Failed to describe instances: {}", e);
-                ProviderError::GeneralError(format!("Failed to describe instances: {}", e))
-            }
-            )?;
-        debug!("Data about EC2 instances obtained successfully.");
+        let instance_ids = (!query.instance_ids.is_empty()).then(|| query.instance_ids.clone());
+        let mut request = self.ec2().describe_instances()
+            .set_filters(Some(ec2_filters(query)))
+            .set_instance_ids(instance_ids)
+            .into_paginator();
+        // EC2 rejects page size combined with explicit instance IDs
+        if let Some(page_size) = query.page_size.filter(|_| query.instance_ids.is_empty()) {
+            request = request.page_size(page_size);
+        }
+        let mut pages = request.send();
 
         // Prepare object that will be returned
-        let mut instance_data: Ec2Response = Ec2Response::new();
-
-        debug!("Processing instances...");
-        for reservation in response.reservations() {
-            for instance in reservation.instances() {
-                let mut name_tag: String = "<unknown>".to_string();
-
-                // obtain instance name
-                debug!("Obtaining instance name");
-                for tag in instance.tags() {
-                    if tag.key == Some("Name".to_string()) {
-                        debug!("Found Name tag");
-                        name_tag = match &tag.value {
-                            Some(value) => value.to_string(),
-                            None => "<unknown>".to_string(),
-                        };
-                        break;
+        let mut instance_data: InstanceList = InstanceList::new();
+
+        // process pages as they arrive, stop early once max_items is reached
+        'pages: while let Some(page) = pages.next().await {
+            let page = page.map_err(|e| self.sdk_error(e, "DescribeInstances"))?;
+            debug!("Processing page with {} reservations", page.reservations().len());
+
+            for reservation in page.reservations() {
+                for instance in reservation.instances() {
+                    let current_instance = parse_instance(instance, self.region());
+                    if !query.matches_name(&current_instance.name) {
+                        debug!("Skipping instance {} not matching name pattern", &current_instance.instance_id);
+                        continue;
                     }
-                }
 
-                debug!("Parsing instance state...");
-                let parsed_state = match &instance.state() {
-                    Some(s) => {
-                        match &s.name {
-                            Some(name) => name.as_str().to_string(),
-                            None => "<unknown>".to_string(),
-                        }
-                    },
-                    None => "<unknown>".to_string(),
-                    
-                };
-
-                debug!("Parsing instance id");
-                let parsed_id = &instance.instance_id().unwrap_or("<unknown>");
-
-                debug!("Parsing private_ip");
-                let parsed_private_ip = &instance.private_ip_address().unwrap_or("<unknown>");
-
-                let current_instance = Ec2Instance {
-                    name: name_tag,
-                    instance_id: parsed_id.to_string(),
-                    state: parsed_state,
-                    private_ip: parsed_private_ip.to_string(),
-                };
-                
-                debug!("Appending instance data for {}", &parsed_id);
-                instance_data.push(current_instance);
+                    if query.max_items.is_some_and(|max| instance_data.instances.len() >= max) {
+                        debug!("Reached max items limit, skipping remaining pages");
+                        break 'pages;
+                    }
+
+                    debug!("Appending instance data for {}", &current_instance.instance_id);
+                    instance_data.push(current_instance);
+                }
             }
         }
+        debug!("Data about EC2 instances obtained successfully.");
         Ok(instance_data)
     }
 
-    pub async fn list_parameters(&self, param_path: Option<String>, decrypt: bool) -> Result<SsmResponse, ProviderError> {
+    async fn resolve_instance(&self, target: &str) -> Result<Instance, ProviderError> {
+        info!("Resolving AWS instance {}...", target);
+
+        // narrow down the listing server-side, by ID or Name tag
+        let query = if target.starts_with("i-") {
+            InstanceQuery { filters: vec![("instance-id".to_string(), target.to_string())], ..Default::default() }
+        } else {
+            InstanceQuery { tags: vec![("Name".to_string(), target.to_string())], ..Default::default() }
+        };
+
+        let instances = self.list_instances(&query).await?;
+        single_instance(target, instances.instances)
+    }
+
+    async fn change_state(&self, instance_ids: &[String], action: InstanceAction, dry_run: bool) -> Result<StateChangeList, ProviderError> {
+        info!("Requesting {} of AWS instances {:?} (dry run: {})...", action, instance_ids, dry_run);
+        let ids = Some(instance_ids.to_vec());
+
+        let result = match action {
+            InstanceAction::Start => self.ec2().start_instances()
+                .set_instance_ids(ids)
+                .dry_run(dry_run)
+                .send()
+                .await
+                .map(|r| parse_state_changes(r.starting_instances()))
+                .map_err(|e| self.state_change_error(e, "StartInstances")),
+
+            InstanceAction::Stop => self.ec2().stop_instances()
+                .set_instance_ids(ids)
+                .dry_run(dry_run)
+                .send()
+                .await
+                .map(|r| parse_state_changes(r.stopping_instances()))
+                .map_err(|e| self.state_change_error(e, "StopInstances")),
+
+            InstanceAction::Terminate => self.ec2().terminate_instances()
+                .set_instance_ids(ids)
+                .dry_run(dry_run)
+                .send()
+                .await
+                .map(|r| parse_state_changes(r.terminating_instances()))
+                .map_err(|e| self.state_change_error(e, "TerminateInstances")),
+
+            // reboot does not report state transitions
+            InstanceAction::Reboot => self.ec2().reboot_instances()
+                .set_instance_ids(ids)
+                .dry_run(dry_run)
+                .send()
+                .await
+                .map(|_| instance_ids.iter()
+                    .map(|id| StateChange {
+                        instance_id: id.clone(),
+                        previous_state: None,
+                        current_state: "rebooting".to_string(),
+                    })
+                    .collect())
+                .map_err(|e| self.state_change_error(e, "RebootInstances")),
+        };
+
+        match result {
+            Ok(changes) => Ok(StateChangeList { changes }),
+            // EC2 reports successful dry run as DryRunOperation error
+            Err(None) => Ok(StateChangeList {
+                changes: instance_ids.iter()
+                    .map(|id| StateChange {
+                        instance_id: id.clone(),
+                        previous_state: None,
+                        current_state: "dry-run-ok".to_string(),
+                    })
+                    .collect(),
+            }),
+            Err(Some(e)) => Err(e),
+        }
+    }
+
+    async fn describe_instance(&self, instance_id: &str) -> Result<InstanceDetails, ProviderError> {
+        info!("Describing AWS instance {}...", instance_id);
+
+        let response = self.ec2().describe_instances()
+            .instance_ids(instance_id)
+            .send()
+            .await
+            .map_err(|e| self.sdk_error(e, "DescribeInstances"))?;
+
+        let instance = response.reservations().iter()
+            .flat_map(|reservation| reservation.instances())
+            .next()
+            .ok_or_else(|| ProviderError::ResourceNotFound(format!("Instance {} not found", instance_id)))?;
+
+        debug!("Obtaining status checks...");
+        let status_response = self.ec2().describe_instance_status()
+            .instance_ids(instance_id)
+            .include_all_instances(true)
+            .send()
+            .await
+            .map_err(|e| self.sdk_error(e, "DescribeInstanceStatus"))?;
+        let status = status_response.instance_statuses().first();
+
+        debug!("Parsing network interfaces...");
+        let network_interfaces = instance.network_interfaces().iter()
+            .map(|interface| NetworkInterface {
+                interface_id: interface.network_interface_id().unwrap_or("<unknown>").to_string(),
+                private_ip: interface.private_ip_address().map(|ip| ip.to_string()),
+                public_ip: interface.association().and_then(|a| a.public_ip()).map(|ip| ip.to_string()),
+                subnet_id: interface.subnet_id().map(|id| id.to_string()),
+                security_groups: interface.groups().iter()
+                    .filter_map(|group| group.group_id())
+                    .map(|id| id.to_string())
+                    .collect(),
+            })
+            .collect();
+
+        let security_groups = instance.security_groups().iter()
+            .map(|group| SecurityGroup {
+                group_id: group.group_id().unwrap_or("<unknown>").to_string(),
+                group_name: group.group_name().map(|name| name.to_string()),
+            })
+            .collect();
+
+        debug!("Parsing block devices...");
+        let block_devices = instance.block_device_mappings().iter()
+            .map(|device| BlockDevice {
+                device_name: device.device_name().unwrap_or("<unknown>").to_string(),
+                volume_id: device.ebs().and_then(|ebs| ebs.volume_id()).map(|id| id.to_string()),
+                status: device.ebs().and_then(|ebs| ebs.status()).map(|s| s.as_str().to_string()),
+                delete_on_termination: device.ebs().and_then(|ebs| ebs.delete_on_termination()),
+            })
+            .collect();
+
+        Ok(InstanceDetails {
+            instance: parse_instance(instance, self.region()),
+            network_interfaces,
+            security_groups,
+            block_devices,
+            monitoring: instance.monitoring().and_then(|m| m.state()).map(|s| s.as_str().to_string()),
+            system_status: status.and_then(|s| s.system_status()).and_then(|s| s.status()).map(|s| s.as_str().to_string()),
+            instance_status: status.and_then(|s| s.instance_status()).and_then(|s| s.status()).map(|s| s.as_str().to_string()),
+        })
+    }
+
+    async fn start_session(&self, request: &SessionRequest) -> Result<Session, ProviderError> {
+        info!("Starting AWS SSM session on {}...", request.target);
+
+        let parameters = (!request.parameters.is_empty())
+            .then(|| request.parameters.clone().into_iter().collect());
+        let response = self.ssm().start_session()
+            .target(&request.target)
+            .set_document_name(request.document_name.clone())
+            .set_parameters(parameters)
+            .send()
+            .await
+            .map_err(|e| self.sdk_error(e, "StartSession"))?;
+
+        Ok(Session {
+            session_id: response.session_id().unwrap_or_default().to_string(),
+            token_value: response.token_value().unwrap_or_default().to_string(),
+            stream_url: response.stream_url().unwrap_or_default().to_string(),
+            region: self.region(),
+            profile: self.profile.clone(),
+        })
+    }
+
+    async fn terminate_session(&self, session_id: &str) -> Result<(), ProviderError> {
+        info!("Terminating AWS SSM session {}...", session_id);
+        self.ssm().terminate_session()
+            .session_id(session_id)
+            .send()
+            .await
+            .map_err(|e| self.sdk_error(e, "TerminateSession"))?;
+        Ok(())
+    }
+
+    async fn send_command(&self, instance_ids: &[String], command: &str, timeout: u64) -> Result<Vec<CommandInvocation>, ProviderError> {
+        info!("Sending AWS SSM command to {} instance(s)...", instance_ids.len());
+
+        let mut invocations = Vec::new();
+        // a single request accepts limited number of instances
+        for batch in instance_ids.chunks(SEND_COMMAND_BATCH_SIZE) {
+            let response = self.ssm().send_command()
+                .set_instance_ids(Some(batch.to_vec()))
+                .document_name(RUN_SHELL_SCRIPT_DOCUMENT)
+                .parameters("commands", vec![command.to_string()])
+                .parameters("executionTimeout", vec![timeout.to_string()])
+                .send()
+                .await
+                .map_err(|e| self.sdk_error(e, "SendCommand"))?;
+
+            let command_id = response.command()
+                .and_then(|c| c.command_id())
+                .unwrap_or("<unknown>")
+                .to_string();
+            debug!("Command {} sent to {:?}", command_id, batch);
+
+            invocations.extend(batch.iter().map(|instance_id| CommandInvocation {
+                command_id: command_id.clone(),
+                instance_id: instance_id.clone(),
+                name: None,
+                status: "Pending".to_string(),
+                exit_code: None,
+                stdout: String::new(),
+                stderr: String::new(),
+            }));
+        }
+        Ok(invocations)
+    }
+
+    async fn get_command_invocation(&self, command_id: &str, instance_id: &str) -> Result<CommandInvocation, ProviderError> {
+        debug!("Fetching AWS SSM command {} invocation on {}...", command_id, instance_id);
+
+        let result = self.ssm().get_command_invocation()
+            .command_id(command_id)
+            .instance_id(instance_id)
+            .send()
+            .await;
+
+        let response = match result {
+            Ok(response) => response,
+            // invocation shows up shortly after the command is sent
+            Err(e) if e.as_service_error().and_then(|e| e.code()) == Some("InvocationDoesNotExist") => {
+                return Ok(CommandInvocation {
+                    command_id: command_id.to_string(),
+                    instance_id: instance_id.to_string(),
+                    name: None,
+                    status: "Pending".to_string(),
+                    exit_code: None,
+                    stdout: String::new(),
+                    stderr: String::new(),
+                });
+            }
+            Err(e) => return Err(self.sdk_error(e, "GetCommandInvocation")),
+        };
+
+        let mut invocation = CommandInvocation {
+            command_id: command_id.to_string(),
+            instance_id: instance_id.to_string(),
+            name: None,
+            status: response.status().map(|s| s.as_str().to_string()).unwrap_or("Pending".to_string()),
+            exit_code: None,
+            stdout: response.standard_output_content().unwrap_or_default().to_string(),
+            stderr: response.standard_error_content().unwrap_or_default().to_string(),
+        };
+        // response code is -1 until the command finishes
+        if invocation.is_finished() && response.response_code() >= 0 {
+            invocation.exit_code = Some(response.response_code());
+        }
+        Ok(invocation)
+    }
+
+    async fn list_parameters(&self, param_path: Option<String>, decrypt: bool, metadata: bool) -> Result<ParameterList, ProviderError> {
         info!("Listing AWS SSM parameters...");
 
-        debug!("Creating SSM client");
-        let config = load_defaults(BehaviorVersion::latest()).await;
-        let client = SSMClient::new(&config);
+        let path = param_path.unwrap_or("/".to_string());
 
         debug!("Obtaining ssm parameters");
-        let response = client.get_parameters_by_path()
-            .path(param_path.unwrap_or("/".to_string()))
+        let mut pages = self.ssm().get_parameters_by_path()
+            .path(&path)
             .recursive(true)
             .with_decryption(decrypt)
-            .send()
-            .await
-            .map_err(|e| {
-                match e {
-                    SdkError::DispatchFailure(_) => {
-                        return ProviderError::ConnectionError;
-                    },
-                    SdkError::TimeoutError(_) => {
-                        return ProviderError::TimeoutError;
-                    },
-                    _ => {
-                        return ProviderError::GeneralError(format!("Failed to get SSM parameters: {}", e));
-                    }
+            .into_paginator()
+            .send();
 
-                }
+        let mut parsed_data: ParameterList = ParameterList::new();
+        while let Some(page) = pages.next().await {
+            let page = page.map_err(|e| self.sdk_error(e, "GetParametersByPath"))?;
+            debug!("Processing page with {} parameters", page.parameters().len());
+
+            for param in page.parameters() {
+                parsed_data.push(parse_parameter(param, decrypt, self.region()));
             }
-            );
-        
-        debug!("SSM parameters obtained successfully");
-        let parsed_data: SsmResponse = response.iter()
-            .flat_map(|page| page.parameters()) // FIXME possible empty iterator, and non-handled errors
-            .map(|param| {
-                let mut parsed_value: String = String::new(); //FIXME try rewrite without mut
-                if param.r#type() == Some(&ParameterType::SecureString) && decrypt {
-                   parsed_value = param.value().unwrap_or("<unknown>").to_string(); 
-                } else if param.r#type() == Some(&ParameterType::SecureString) && !decrypt {
-                   parsed_value = "<encrypted>".to_string();
-                } else {
-                   parsed_value = param.value().unwrap_or("<unknown>").to_string();
-                }
+        }
 
-                SsmParameter {
-                    name: param.name().unwrap_or("<unknown>").to_string(),
-                    r#type: param.r#type().map(|t| t.as_str().to_string()).unwrap_or("?".to_string()),
-                    value: parsed_value,
-                }
-            })
-            .collect();
+        if parsed_data.parameters.is_empty() {
+            debug!("No SSM parameters found under {}", &path);
+            return Err(ProviderError::ResourceNotFound(format!("No parameters found under path {}", path)));
+        }
+
+        if metadata {
+            self.add_parameter_metadata(&path, &mut parsed_data.parameters).await?;
+        }
 
         debug!("Parsed SSM parameters successfully");
         Ok(parsed_data)
     }
+
+    async fn get_parameter(&self, selector: &str, decrypt: bool) -> Result<Parameter, ProviderError> {
+        info!("Fetching AWS SSM parameter {}...", selector);
+
+        // version and label selectors are resolved by the service itself
+        let response = self.ssm().get_parameter()
+            .name(selector)
+            .with_decryption(decrypt)
+            .send()
+            .await
+            .map_err(|e| parameter_error(self.sdk_error(e, "GetParameter"), selector))?;
+
+        response.parameter()
+            .map(|param| parse_parameter(param, decrypt, self.region()))
+            .ok_or_else(|| ProviderError::ResourceNotFound(format!("Parameter {} not found", selector)))
+    }
+
+    async fn describe_parameter(&self, name: &str) -> Result<ParameterDefinition, ProviderError> {
+        info!("Describing AWS SSM parameter {}...", name);
+
+        let response = self.ssm().get_parameter()
+            .name(name)
+            .with_decryption(true)
+            .send()
+            .await
+            .map_err(|e| parameter_error(self.sdk_error(e, "GetParameter"), name))?;
+        let parameter = response.parameter()
+            .ok_or_else(|| ProviderError::ResourceNotFound(format!("Parameter {} not found", name)))?;
+
+        debug!("Obtaining metadata of parameter {}", name);
+        let filter = ParameterStringFilter::builder()
+            .key("Name")
+            .option("Equals")
+            .values(name)
+            .build()
+            .map_err(|e| ProviderError::GeneralError(e.to_string()))?;
+        let metadata = self.ssm().describe_parameters()
+            .parameter_filters(filter)
+            .send()
+            .await
+            .map_err(|e| self.sdk_error(e, "DescribeParameters"))?;
+        let metadata = metadata.parameters().first();
+
+        debug!("Obtaining tags of parameter {}", name);
+        let tags = self.ssm().list_tags_for_resource()
+            .resource_type(ResourceTypeForTagging::Parameter)
+            .resource_id(name)
+            .send()
+            .await
+            .map_err(|e| self.sdk_error(e, "ListTagsForResource"))?;
+
+        Ok(ParameterDefinition {
+            name: name.to_string(),
+            r#type: parameter.r#type().map(|t| t.as_str().to_string()).unwrap_or("String".to_string()),
+            value: parameter.value().unwrap_or_default().to_string(),
+            kms_key_id: metadata.and_then(|m| m.key_id()).map(|key| key.to_string()),
+            description: metadata.and_then(|m| m.description()).map(|description| description.to_string()),
+            tags: tags.tag_list().iter()
+                .map(|tag| (tag.key().to_string(), tag.value().to_string()))
+                .collect(),
+        })
+    }
+
+    async fn get_parameter_history(&self, name: &str, decrypt: bool) -> Result<ParameterHistory, ProviderError> {
+        info!("Fetching AWS SSM parameter {} history...", name);
+
+        let mut pages = self.ssm().get_parameter_history()
+            .name(name)
+            .with_decryption(decrypt)
+            .into_paginator()
+            .send();
+
+        let mut versions = Vec::new();
+        while let Some(page) = pages.next().await {
+            let page = page.map_err(|e| parameter_error(self.sdk_error(e, "GetParameterHistory"), name))?;
+            debug!("Processing page with {} versions", page.parameters().len());
+
+            for item in page.parameters() {
+                let secure = item.r#type() == Some(&ParameterType::SecureString);
+                versions.push(ParameterVersion {
+                    name: item.name().unwrap_or(name).to_string(),
+                    version: item.version(),
+                    r#type: item.r#type().map(|t| t.as_str().to_string()).unwrap_or("?".to_string()),
+                    value: if secure && !decrypt {
+                        "<encrypted>".to_string()
+                    } else {
+                        item.value().unwrap_or_default().to_string()
+                    },
+                    last_modified: item.last_modified_date().and_then(|t| t.fmt(DateTimeFormat::DateTime).ok()),
+                    modified_by: item.last_modified_user().map(|user| user.to_string()),
+                    labels: item.labels().to_vec(),
+                    kms_key_id: item.key_id().map(|key| key.to_string()),
+                    description: item.description().map(|description| description.to_string()),
+                });
+            }
+        }
+
+        versions.sort_by_key(|version| version.version);
+        Ok(ParameterHistory { versions })
+    }
+
+    async fn put_parameter(&self, definition: &ParameterDefinition, overwrite: bool) -> Result<ParameterChange, ProviderError> {
+        info!("Putting AWS SSM parameter {}...", definition.name);
+
+        let response = self.ssm().put_parameter()
+            .name(&definition.name)
+            .value(&definition.value)
+            .r#type(ParameterType::from(definition.r#type.as_str()))
+            .set_key_id(definition.kms_key_id.clone())
+            .set_description(definition.description.clone())
+            .overwrite(overwrite)
+            .send()
+            .await
+            .map_err(|e| self.sdk_error(e, "PutParameter"))?;
+
+        // tags cannot be passed together with overwrite, so they are always added separately
+        if !definition.tags.is_empty() {
+            debug!("Tagging parameter {}", definition.name);
+            let tags = definition.tags.iter()
+                .map(|(key, value)| Tag::builder().key(key).value(value).build())
+                .collect::<Result<Vec<Tag>, _>>()
+                .map_err(|e| ProviderError::GeneralError(e.to_string()))?;
+            self.ssm().add_tags_to_resource()
+                .resource_type(ResourceTypeForTagging::Parameter)
+                .resource_id(&definition.name)
+                .set_tags(Some(tags))
+                .send()
+                .await
+                .map_err(|e| self.sdk_error(e, "AddTagsToResource"))?;
+        }
+
+        let version = response.version();
+        Ok(ParameterChange {
+            name: definition.name.clone(),
+            action: if version == 1 { "created" } else { "updated" }.to_string(),
+            version: Some(version),
+        })
+    }
+
+    async fn delete_parameters(&self, names: &[String]) -> Result<ParameterChangeList, ProviderError> {
+        info!("Deleting {} AWS SSM parameter(s)...", names.len());
+
+        let mut changes = Vec::new();
+        // a single request accepts limited number of parameters
+        for batch in names.chunks(DELETE_PARAMETERS_BATCH_SIZE) {
+            let response = self.ssm().delete_parameters()
+                .set_names(Some(batch.to_vec()))
+                .send()
+                .await
+                .map_err(|e| self.sdk_error(e, "DeleteParameters"))?;
+
+            let deleted = response.deleted_parameters().iter().map(|name| (name, "deleted"));
+            let invalid = response.invalid_parameters().iter().map(|name| (name, "not found"));
+            changes.extend(deleted.chain(invalid).map(|(name, action)| ParameterChange {
+                name: name.clone(),
+                action: action.to_string(),
+                version: None,
+            }));
+        }
+        Ok(ParameterChangeList { changes })
+    }
+
+    async fn list_regions(&self) -> Result<Vec<String>, ProviderError> {
+        info!("Listing AWS regions...");
+
+        let response = self.ec2().describe_regions()
+            .send()
+            .await
+            .map_err(|e| self.sdk_error(e, "DescribeRegions"))?;
+
+        Ok(response.regions().iter()
+            .filter_map(|region| region.region_name())
+            .map(|name| name.to_string())
+            .collect())
+    }
+
+    fn with_region(&self, region: &str) -> Self {
+        debug!("Binding AWS provider to region {}", region);
+        Self::from_config(
+            self.config.to_builder().region(Region::new(region.to_string())).build(),
+            self.profile.clone(),
+        )
+    }
+
+    async fn with_profile(&self, profile: &str) -> Self {
+        debug!("Switching AWS provider to profile {}", profile);
+        Self::new(Some(profile.to_string()), self.region()).await
+    }
+
+    async fn assume_role(&self, role: &str) -> Result<Self, ProviderError> {
+        info!("Assuming AWS role {}...", role);
+
+        let credentials = AssumeRoleProvider::builder(role)
+            .session_name("ctk")
+            .configure(&self.config)
+            .build()
+            .await;
+
+        // resolve credentials right away, so that failure is attributed to this role
+        credentials.provide_credentials()
+            .await
+            .map_err(|e| {
+                error!("Failed to assume role {}: {}", role, e);
+                map_credentials_error(&e, &self.profile)
+            })?;
+
+        Ok(Self::from_config(
+            self.config.to_builder()
+                .credentials_provider(SharedCredentialsProvider::new(credentials))
+                .build(),
+            self.profile.clone(),
+        ))
+    }
+}
+
+/// Convert SDK state transitions into provider-neutral ones
+fn parse_state_changes(changes: &[InstanceStateChange]) -> Vec<StateChange> {
+    changes.iter()
+        .map(|change| StateChange {
+            instance_id: change.instance_id().unwrap_or("<unknown>").to_string(),
+            previous_state: change.previous_state().and_then(|s| s.name()).map(|name| name.as_str().to_string()),
+            current_state: change.current_state()
+                .and_then(|s| s.name())
+                .map(|name| name.as_str().to_string())
+                .unwrap_or("<unknown>".to_string()),
+        })
+        .collect()
+}
+
+/// Translate instance query into server-side EC2 filters, comma separates alternative values
+fn ec2_filters(query: &InstanceQuery) -> Vec<Filter> {
+    let filter = |name: String, values: &str| {
+        Filter::builder()
+            .name(name)
+            .set_values(Some(values.split(',').map(|v| v.to_string()).collect()))
+            .build()
+    };
+
+    let mut filters: Vec<Filter> = Vec::new();
+    for (name, value) in &query.filters {
+        filters.push(filter(name.clone(), value));
+    }
+    for (key, value) in &query.tags {
+        filters.push(filter(format!("tag:{}", key), value));
+    }
+    if !query.states.is_empty() {
+        filters.push(filter("instance-state-name".to_string(), &query.states.join(",")));
+    }
+    if let Some(vpc) = &query.vpc {
+        filters.push(filter("vpc-id".to_string(), vpc));
+    }
+    if let Some(subnet) = &query.subnet {
+        filters.push(filter("subnet-id".to_string(), subnet));
+    }
+
+    debug!("Using EC2 filters: {:?}", filters);
+    filters
+}
+
+/// Convert SDK instance description into provider-neutral instance
+fn parse_instance(instance: &Ec2Instance, region: Option<String>) -> Instance {
+    // obtain instance name
+    debug!("Obtaining instance name");
+    let name_tag = instance.tags().iter()
+        .find(|tag| tag.key() == Some("Name"))
+        .and_then(|tag| tag.value())
+        .unwrap_or("<unknown>")
+        .to_string();
+
+    debug!("Parsing instance state...");
+    let parsed_state = instance.state()
+        .and_then(|s| s.name())
+        .map(|name| name.as_str().to_string())
+        .unwrap_or("<unknown>".to_string());
+
+    let tags: BTreeMap<String, String> = instance.tags().iter()
+        .filter_map(|tag| Some((tag.key()?.to_string(), tag.value().unwrap_or("").to_string())))
+        .collect();
+
+    Instance {
+        name: name_tag,
+        instance_id: instance.instance_id().unwrap_or("<unknown>").to_string(),
+        state: parsed_state,
+        private_ip: instance.private_ip_address().unwrap_or("<unknown>").to_string(),
+        region,
+        account: None,
+        role: None,
+        instance_type: instance.instance_type().map(|t| t.as_str().to_string()),
+        availability_zone: instance.placement().and_then(|p| p.availability_zone()).map(|az| az.to_string()),
+        public_ip: instance.public_ip_address().map(|ip| ip.to_string()),
+        launch_time: instance.launch_time().and_then(|t| t.fmt(DateTimeFormat::DateTime).ok()),
+        image_id: instance.image_id().map(|id| id.to_string()),
+        key_name: instance.key_name().map(|key| key.to_string()),
+        vpc_id: instance.vpc_id().map(|id| id.to_string()),
+        subnet_id: instance.subnet_id().map(|id| id.to_string()),
+        iam_profile: instance.iam_instance_profile().and_then(|p| p.arn()).map(|arn| arn.to_string()),
+        platform: instance.platform_details().map(|p| p.to_string()),
+        tags,
+    }
 }
 
+/// Report missing parameter as such, instead of generic service error
+fn parameter_error(error: ProviderError, name: &str) -> ProviderError {
+    match error {
+        ProviderError::ServiceError { code, .. } if PARAMETER_NOT_FOUND_CODES.contains(&code.as_str()) => {
+            ProviderError::ResourceNotFound(format!("Parameter {} not found", name))
+        }
+        other => other,
+    }
+}
+
+/// Convert SDK parameter into provider-neutral parameter, hiding secure values unless decrypted
+fn parse_parameter(param: &SsmParameter, decrypt: bool, region: Option<String>) -> Parameter {
+    let parsed_value = if param.r#type() == Some(&ParameterType::SecureString) && !decrypt {
+        "<encrypted>".to_string()
+    } else {
+        param.value().unwrap_or("<unknown>").to_string()
+    };
+
+    Parameter {
+        name: param.name().unwrap_or("<unknown>").to_string(),
+        r#type: param.r#type().map(|t| t.as_str().to_string()).unwrap_or("?".to_string()),
+        value: parsed_value,
+        region,
+        account: None,
+        role: None,
+        version: Some(param.version()),
+        last_modified: param.last_modified_date().and_then(|t| t.fmt(DateTimeFormat::DateTime).ok()),
+        data_type: param.data_type().map(|data_type| data_type.to_string()),
+        modified_by: None,
+        tier: None,
+        kms_key_id: None,
+        description: None,
+    }
+}
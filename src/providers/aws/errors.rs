@@ -0,0 +1,120 @@
+use crate::actions::ProviderError;
+use log::{debug, error};
+
+use std::error::Error;
+
+use aws_credential_types::provider::error::CredentialsError;
+use aws_sdk_sts::error::{DisplayErrorContext, ProvideErrorMetadata, SdkError};
+
+/// Service error codes returned when caller lacks permissions
+const ACCESS_DENIED_CODES: [&str; 4] = ["AccessDenied", "AccessDeniedException", "UnauthorizedOperation", "UnauthorizedAccess"];
+
+/// Service error codes returned when requests are rate limited
+const THROTTLING_CODES: [&str; 5] = ["Throttling", "ThrottlingException", "RequestLimitExceeded", "TooManyRequestsException", "ThrottledException"];
+
+/// Service error codes returned for expired session credentials
+const EXPIRED_TOKEN_CODES: [&str; 2] = ["ExpiredToken", "ExpiredTokenException"];
+
+/// Service error codes returned for credentials unknown to AWS
+const INVALID_TOKEN_CODES: [&str; 2] = ["InvalidClientTokenId", "UnrecognizedClientException"];
+
+/// Convert any AWS SDK error into provider error, keeping details useful for remediation
+pub fn map_sdk_error<E, R>(err: SdkError<E, R>, operation: &str, profile: &Option<String>, region: &Option<String>) -> ProviderError
+where
+    E: ProvideErrorMetadata + Error + 'static,
+    R: std::fmt::Debug + 'static,
+{
+    let details = DisplayErrorContext(&err).to_string();
+    error!("{} failed: {}", operation, details);
+
+    if let Some(credentials) = find_credentials_error(&err) {
+        return map_credentials_error(credentials, profile);
+    }
+
+    match &err {
+        SdkError::TimeoutError(_) => ProviderError::TimeoutError,
+
+        SdkError::DispatchFailure(failure) => {
+            if failure.is_timeout() {
+                ProviderError::TimeoutError
+            } else if is_dns_failure(&details) {
+                debug!("Endpoint for region {:?} cannot be resolved", region);
+                ProviderError::UnknownRegion(region.clone().unwrap_or("<none>".to_string()))
+            } else {
+                ProviderError::EndpointError(details)
+            }
+        }
+
+        SdkError::ServiceError(service) => {
+            let service_error = service.err();
+            let code = service_error.code().unwrap_or("Unknown").to_string();
+            let message = service_error.message().unwrap_or("").to_string();
+            let request_id = service_error.meta().extra("aws_request_id").map(|id| id.to_string());
+            debug!("Service error code {}, request ID {:?}", code, request_id);
+
+            if ACCESS_DENIED_CODES.contains(&code.as_str()) {
+                ProviderError::AccessDenied {
+                    action: denied_action(&message),
+                    request_id,
+                }
+            } else if THROTTLING_CODES.contains(&code.as_str()) {
+                ProviderError::Throttled { request_id }
+            } else if EXPIRED_TOKEN_CODES.contains(&code.as_str()) {
+                ProviderError::SsoTokenExpired(profile.clone())
+            } else if INVALID_TOKEN_CODES.contains(&code.as_str()) {
+                ProviderError::AuthenticationError
+            } else {
+                ProviderError::ServiceError {
+                    code,
+                    message,
+                    request_id,
+                }
+            }
+        }
+
+        _ => ProviderError::GeneralError(format!("{} failed: {}", operation, details)),
+    }
+}
+
+/// Convert credential resolution failure into provider error
+pub fn map_credentials_error(err: &CredentialsError, profile: &Option<String>) -> ProviderError {
+    let details = DisplayErrorContext(err).to_string();
+    debug!("Credentials error: {}", details);
+
+    match err {
+        CredentialsError::CredentialsNotLoaded(_) => ProviderError::MissingCredentials(profile.clone()),
+        CredentialsError::ProviderTimedOut(_) => ProviderError::TimeoutError,
+        _ if is_expired_session(&details) => ProviderError::SsoTokenExpired(profile.clone()),
+        _ => ProviderError::GeneralError(format!("Cannot load credentials: {}", details)),
+    }
+}
+
+/// Look for credentials error anywhere in the chain of error sources
+fn find_credentials_error<'a>(err: &'a (dyn Error + 'static)) -> Option<&'a CredentialsError> {
+    let mut current: Option<&(dyn Error + 'static)> = Some(err);
+    while let Some(e) = current {
+        if let Some(credentials) = e.downcast_ref::<CredentialsError>() {
+            return Some(credentials);
+        }
+        current = e.source();
+    }
+    None
+}
+
+/// Extract IAM action from denial message (`... is not authorized to perform: ec2:DescribeInstances ...`)
+fn denied_action(message: &str) -> Option<String> {
+    message.split("perform: ")
+        .nth(1)
+        .and_then(|rest| rest.split_whitespace().next())
+        .map(|action| action.trim_end_matches([',', '.']).to_string())
+}
+
+fn is_dns_failure(details: &str) -> bool {
+    let details = details.to_lowercase();
+    details.contains("dns error") || details.contains("failed to lookup address")
+}
+
+fn is_expired_session(details: &str) -> bool {
+    let details = details.to_lowercase();
+    details.contains("expired") || details.contains("sso session")
+}
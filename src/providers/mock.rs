@@ -0,0 +1,462 @@
+use crate::accounts::account_id;
+use crate::actions::{ProviderActions, ProviderError, Identity, InstanceDetails, InstanceList, InstanceQuery, Parameter, ParameterList};
+use crate::actions::{InstanceAction, StateChange, StateChangeList};
+use crate::actions::{CommandInvocation, Session, SessionRequest};
+use crate::actions::{ParameterChange, ParameterChangeList, ParameterDefinition, ParameterHistory, ParameterVersion};
+use log::{info, debug, error, warn};
+use serde::de::DeserializeOwned;
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// Provider serving data from local JSON fixtures instead of a real cloud
+///
+/// Fixture directory is expected to contain `identity.json`, `instances.json`,
+/// `parameters.json` and `session.json` files, using the same layout as `--output json`.
+#[derive(Debug)]
+pub struct MockProvider {
+    fixtures: PathBuf,
+    region: Option<String>,
+    account: Option<String>,
+    /// Instance states changed by lifecycle actions, kept for the lifetime of the provider
+    states: Mutex<HashMap<String, String>>,
+    /// Shell commands sent to instances, by command ID
+    commands: Mutex<HashMap<String, String>>,
+    /// Parameters changed by write actions, fixture content until the first write
+    parameters: Mutex<Option<Vec<Parameter>>>,
+    /// Every version of parameters written so far, fixture parameters are at version 1
+    history: Mutex<HashMap<String, Vec<ParameterVersion>>>,
+}
+
+impl MockProvider {
+    pub fn new(fixtures: PathBuf) -> Self {
+        Self::scoped(fixtures, None, None)
+    }
+
+    fn scoped(fixtures: PathBuf, region: Option<String>, account: Option<String>) -> Self {
+        MockProvider {
+            fixtures,
+            region,
+            account,
+            states: Mutex::new(HashMap::new()),
+            commands: Mutex::new(HashMap::new()),
+            parameters: Mutex::new(None),
+            history: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Versions of a stored parameter, oldest first
+    fn parameter_history(&self, parameter: &Parameter) -> Vec<ParameterVersion> {
+        self.history.lock().unwrap()
+            .get(&parameter.name)
+            .cloned()
+            .unwrap_or_else(|| vec![first_version(parameter)])
+    }
+
+    /// Mimic the service: version and modification time come with every parameter,
+    /// the rest of metadata only when parameters are described as well
+    fn with_metadata(&self, parameter: Parameter, metadata: bool) -> Parameter {
+        let latest = self.parameter_history(&parameter).pop().unwrap_or_else(|| first_version(&parameter));
+        Parameter {
+            version: Some(latest.version),
+            last_modified: latest.last_modified,
+            data_type: Some(parameter.data_type.clone().unwrap_or("text".to_string())),
+            modified_by: if metadata { latest.modified_by } else { None },
+            tier: if metadata { Some(parameter.tier.clone().unwrap_or("Standard".to_string())) } else { None },
+            kms_key_id: if metadata { latest.kms_key_id } else { None },
+            description: if metadata { latest.description } else { None },
+            ..parameter
+        }
+    }
+
+    /// Current parameters, including changes made by write actions
+    fn stored_parameters(&self) -> Result<Vec<Parameter>, ProviderError> {
+        if let Some(parameters) = self.parameters.lock().unwrap().as_ref() {
+            return Ok(parameters.clone());
+        }
+        let data: ParameterList = self.load("parameters.json")?;
+        Ok(data.parameters)
+    }
+
+    /// Check whether fixture item belongs to the region and account this provider is bound to
+    fn in_scope(&self, region: &Option<String>, account: &Option<String>) -> bool {
+        (self.region.is_none() || &self.region == region)
+            && (self.account.is_none() || &self.account == account)
+    }
+
+    /// Read and deserialize single fixture file
+    fn load<T: DeserializeOwned>(&self, file_name: &str) -> Result<T, ProviderError> {
+        let path = self.fixtures.join(file_name);
+        debug!("Loading fixture {}", path.display());
+
+        if !self.fixtures.is_dir() {
+            error!("Fixture directory {} does not exist", self.fixtures.display());
+            return Err(ProviderError::ConfigurationError);
+        }
+
+        let content = fs::read_to_string(&path).map_err(|e| {
+            error!("Failed to read fixture {}: {}", path.display(), e);
+            ProviderError::ResourceNotFound(format!("Fixture {} not found", path.display()))
+        })?;
+
+        serde_json::from_str(&content).map_err(|e| {
+            error!("Failed to parse fixture {}: {}", path.display(), e);
+            ProviderError::GeneralError(format!("Invalid fixture {}: {}", path.display(), e))
+        })
+    }
+}
+
+impl ProviderActions for MockProvider {
+    async fn who_am_i(&self) -> Result<Identity, ProviderError> {
+        info!("Fetching mock identity...");
+        self.load("identity.json")
+    }
+
+    async fn list_instances(&self, query: &InstanceQuery) -> Result<InstanceList, ProviderError> {
+        info!("Listing mock instances...");
+        let data: InstanceList = self.load("instances.json")?;
+
+        if !query.filters.is_empty() {
+            warn!("Mock provider does not support raw filters, ignoring them");
+        }
+
+        let states = self.states.lock().unwrap();
+        let parsed_data: InstanceList = data.instances.into_iter()
+            .map(|mut instance| {
+                if let Some(state) = states.get(&instance.instance_id) {
+                    instance.state = state.clone();
+                }
+                instance
+            })
+            .filter(|instance| self.in_scope(&instance.region, &instance.account))
+            .filter(|instance| query.instance_ids.is_empty() || query.instance_ids.contains(&instance.instance_id))
+            .filter(|instance| query.states.is_empty() || query.states.contains(&instance.state))
+            .filter(|instance| query.matches_name(&instance.name))
+            .filter(|instance| query.tags.iter().all(|(key, value)| {
+                instance.tags.get(key).is_some_and(|tag| value.split(',').any(|v| v == tag))
+            }))
+            .filter(|instance| query.vpc.is_none() || instance.vpc_id == query.vpc)
+            .filter(|instance| query.subnet.is_none() || instance.subnet_id == query.subnet)
+            .take(query.max_items.unwrap_or(usize::MAX))
+            .collect();
+
+        Ok(parsed_data)
+    }
+
+    async fn change_state(&self, instance_ids: &[String], action: InstanceAction, dry_run: bool) -> Result<StateChangeList, ProviderError> {
+        info!("Requesting {} of mock instances {:?} (dry run: {})...", action, instance_ids, dry_run);
+        let query = InstanceQuery { instance_ids: instance_ids.to_vec(), ..Default::default() };
+        let instances = self.list_instances(&query).await?;
+
+        let mut states = self.states.lock().unwrap();
+        let changes = instances.instances.into_iter()
+            .map(|instance| {
+                let current_state = if dry_run {
+                    "dry-run-ok".to_string()
+                } else {
+                    // mock transitions happen immediately
+                    states.insert(instance.instance_id.clone(), action.target_state().to_string());
+                    action.target_state().to_string()
+                };
+
+                StateChange {
+                    instance_id: instance.instance_id,
+                    previous_state: Some(instance.state),
+                    current_state,
+                }
+            })
+            .collect();
+
+        Ok(StateChangeList { changes })
+    }
+
+    async fn describe_instance(&self, instance_id: &str) -> Result<InstanceDetails, ProviderError> {
+        info!("Describing mock instance {}...", instance_id);
+        let instance = self.resolve_instance(instance_id).await?;
+
+        // fixtures carry only listing data, details are left empty
+        Ok(InstanceDetails {
+            instance,
+            network_interfaces: Vec::new(),
+            security_groups: Vec::new(),
+            block_devices: Vec::new(),
+            monitoring: None,
+            system_status: None,
+            instance_status: None,
+        })
+    }
+
+    async fn start_session(&self, request: &SessionRequest) -> Result<Session, ProviderError> {
+        info!("Starting mock session on {}...", request.target);
+        self.resolve_instance(&request.target).await?;
+
+        // fixture points to a stand-in server, started with `ctk session-server`
+        let mut session: Session = self.load("session.json")?;
+        session.region = self.region.clone().or(session.region);
+        Ok(session)
+    }
+
+    async fn terminate_session(&self, session_id: &str) -> Result<(), ProviderError> {
+        info!("Terminating mock session {}...", session_id);
+        Ok(())
+    }
+
+    async fn send_command(&self, instance_ids: &[String], command: &str, _timeout: u64) -> Result<Vec<CommandInvocation>, ProviderError> {
+        info!("Sending mock command to {} instance(s)...", instance_ids.len());
+        let mut commands = self.commands.lock().unwrap();
+        let command_id = format!("mock-command-{}", commands.len() + 1);
+        commands.insert(command_id.clone(), command.to_string());
+
+        Ok(instance_ids.iter()
+            .map(|instance_id| CommandInvocation {
+                command_id: command_id.clone(),
+                instance_id: instance_id.clone(),
+                name: None,
+                status: "Pending".to_string(),
+                exit_code: None,
+                stdout: String::new(),
+                stderr: String::new(),
+            })
+            .collect())
+    }
+
+    async fn get_command_invocation(&self, command_id: &str, instance_id: &str) -> Result<CommandInvocation, ProviderError> {
+        debug!("Fetching mock command {} invocation on {}...", command_id, instance_id);
+        let command = self.commands.lock().unwrap().get(command_id).cloned()
+            .ok_or_else(|| ProviderError::ResourceNotFound(format!("Command {} not found", command_id)))?;
+        let instance = self.resolve_instance(instance_id).await?;
+
+        // mock commands finish immediately, only on running instances
+        let (status, exit_code, stdout) = if instance.state == "running" {
+            ("Success", Some(0), format!("mock output of '{}' on {}\n", command, instance.name))
+        } else {
+            ("Undeliverable", None, String::new())
+        };
+
+        Ok(CommandInvocation {
+            command_id: command_id.to_string(),
+            instance_id: instance_id.to_string(),
+            name: None,
+            status: status.to_string(),
+            exit_code,
+            stdout,
+            stderr: String::new(),
+        })
+    }
+
+    async fn list_parameters(&self, param_path: Option<String>, decrypt: bool, metadata: bool) -> Result<ParameterList, ProviderError> {
+        info!("Listing mock parameters...");
+        let parameters = self.stored_parameters()?;
+        let path = param_path.unwrap_or("/".to_string());
+
+        // mimic get_parameters_by_path: filter by prefix and hide secure values
+        let parsed_data: ParameterList = parameters.into_iter()
+            .filter(|param| param.name.starts_with(&path) && self.in_scope(&param.region, &param.account))
+            .map(|param| self.with_metadata(hide_secure(param, decrypt), metadata))
+            .collect();
+
+        if parsed_data.parameters.is_empty() {
+            return Err(ProviderError::ResourceNotFound(format!("No parameters found under path {}", path)));
+        }
+
+        Ok(parsed_data)
+    }
+
+    async fn get_parameter(&self, selector: &str, decrypt: bool) -> Result<Parameter, ProviderError> {
+        info!("Fetching mock parameter {}...", selector);
+        let (name, qualifier) = match selector.split_once(':') {
+            Some((name, qualifier)) => (name, Some(qualifier)),
+            None => (selector, None),
+        };
+
+        let parameter = self.stored_parameters()?.into_iter()
+            .find(|param| param.name == name && self.in_scope(&param.region, &param.account))
+            .ok_or_else(|| ProviderError::ResourceNotFound(format!("Parameter {} not found", selector)))?;
+
+        let Some(qualifier) = qualifier else {
+            return Ok(hide_secure(parameter, decrypt));
+        };
+        let selected = self.parameter_history(&parameter).into_iter()
+            .find(|version| version.version.to_string() == qualifier || version.labels.iter().any(|label| label == qualifier))
+            .ok_or_else(|| ProviderError::ResourceNotFound(format!("Parameter {} not found", selector)))?;
+
+        Ok(hide_secure(Parameter {
+            r#type: selected.r#type,
+            value: selected.value,
+            ..parameter
+        }, decrypt))
+    }
+
+    async fn describe_parameter(&self, name: &str) -> Result<ParameterDefinition, ProviderError> {
+        info!("Describing mock parameter {}...", name);
+        let parameter = self.stored_parameters()?.into_iter()
+            .find(|param| param.name == name && self.in_scope(&param.region, &param.account))
+            .ok_or_else(|| ProviderError::ResourceNotFound(format!("Parameter {} not found", name)))?;
+
+        let current = self.parameter_history(&parameter).pop();
+        Ok(ParameterDefinition {
+            name: parameter.name,
+            r#type: parameter.r#type,
+            value: parameter.value,
+            kms_key_id: current.as_ref().and_then(|version| version.kms_key_id.clone()),
+            description: current.and_then(|version| version.description),
+            tags: Vec::new(),
+        })
+    }
+
+    async fn get_parameter_history(&self, name: &str, decrypt: bool) -> Result<ParameterHistory, ProviderError> {
+        info!("Fetching mock parameter {} history...", name);
+        let parameter = self.stored_parameters()?.into_iter()
+            .find(|param| param.name == name && self.in_scope(&param.region, &param.account))
+            .ok_or_else(|| ProviderError::ResourceNotFound(format!("Parameter {} not found", name)))?;
+
+        let versions = self.parameter_history(&parameter).into_iter()
+            .map(|version| if version.r#type == "SecureString" && !decrypt {
+                ParameterVersion { value: "<encrypted>".to_string(), ..version }
+            } else {
+                version
+            })
+            .collect();
+        Ok(ParameterHistory { versions })
+    }
+
+    async fn put_parameter(&self, definition: &ParameterDefinition, overwrite: bool) -> Result<ParameterChange, ProviderError> {
+        info!("Putting mock parameter {}...", definition.name);
+        let mut parameters = self.stored_parameters()?;
+        let existing = parameters.iter()
+            .position(|param| param.name == definition.name && self.in_scope(&param.region, &param.account));
+
+        let (action, mut history) = match existing {
+            Some(_) if !overwrite => return Err(ProviderError::ServiceError {
+                code: "ParameterAlreadyExists".to_string(),
+                message: format!("Parameter {} already exists", definition.name),
+                request_id: None,
+            }),
+            Some(index) => {
+                let history = self.parameter_history(&parameters[index]);
+                parameters[index].r#type = definition.r#type.clone();
+                parameters[index].value = definition.value.clone();
+                ("updated", history)
+            }
+            None => {
+                parameters.push(Parameter {
+                    name: definition.name.clone(),
+                    r#type: definition.r#type.clone(),
+                    value: definition.value.clone(),
+                    region: self.region.clone(),
+                    account: self.account.clone(),
+                    role: None,
+                    version: None,
+                    last_modified: None,
+                    data_type: None,
+                    modified_by: None,
+                    tier: None,
+                    kms_key_id: None,
+                    description: None,
+                });
+                ("created", Vec::new())
+            }
+        };
+
+        let version = history.last().map(|version| version.version).unwrap_or(0) + 1;
+        history.push(ParameterVersion {
+            name: definition.name.clone(),
+            version,
+            r#type: definition.r#type.clone(),
+            value: definition.value.clone(),
+            last_modified: None,
+            modified_by: None,
+            labels: Vec::new(),
+            kms_key_id: definition.kms_key_id.clone(),
+            description: definition.description.clone(),
+        });
+        self.history.lock().unwrap().insert(definition.name.clone(), history);
+        *self.parameters.lock().unwrap() = Some(parameters);
+        Ok(ParameterChange {
+            name: definition.name.clone(),
+            action: action.to_string(),
+            version: Some(version),
+        })
+    }
+
+    async fn delete_parameters(&self, names: &[String]) -> Result<ParameterChangeList, ProviderError> {
+        info!("Deleting {} mock parameter(s)...", names.len());
+        let mut parameters = self.stored_parameters()?;
+
+        let mut changes = Vec::new();
+        for name in names {
+            let before = parameters.len();
+            parameters.retain(|param| !(&param.name == name && self.in_scope(&param.region, &param.account)));
+            self.history.lock().unwrap().remove(name);
+            changes.push(ParameterChange {
+                name: name.clone(),
+                action: if parameters.len() < before { "deleted" } else { "not found" }.to_string(),
+                version: None,
+            });
+        }
+
+        *self.parameters.lock().unwrap() = Some(parameters);
+        Ok(ParameterChangeList { changes })
+    }
+
+    async fn list_regions(&self) -> Result<Vec<String>, ProviderError> {
+        info!("Listing mock regions...");
+        let data: InstanceList = self.load("instances.json")?;
+
+        let mut regions: Vec<String> = data.instances.into_iter()
+            .filter_map(|instance| instance.region)
+            .collect();
+        regions.sort();
+        regions.dedup();
+
+        Ok(regions)
+    }
+
+    fn with_region(&self, region: &str) -> Self {
+        Self::scoped(self.fixtures.clone(), Some(region.to_string()), self.account.clone())
+    }
+
+    async fn with_profile(&self, profile: &str) -> Self {
+        // fixtures are shared by all profiles
+        debug!("Switching mock provider to profile {}", profile);
+        Self::scoped(self.fixtures.clone(), self.region.clone(), self.account.clone())
+    }
+
+    async fn assume_role(&self, role: &str) -> Result<Self, ProviderError> {
+        info!("Assuming mock role {}...", role);
+        let account = account_id(role).ok_or_else(|| {
+            error!("Cannot obtain account ID from role {}", role);
+            ProviderError::AuthenticationError
+        })?;
+
+        Ok(Self::scoped(self.fixtures.clone(), self.region.clone(), Some(account)))
+    }
+}
+
+/// Only version of a fixture parameter which has not been written yet
+fn first_version(parameter: &Parameter) -> ParameterVersion {
+    ParameterVersion {
+        name: parameter.name.clone(),
+        version: parameter.version.unwrap_or(1),
+        r#type: parameter.r#type.clone(),
+        value: parameter.value.clone(),
+        last_modified: parameter.last_modified.clone(),
+        modified_by: parameter.modified_by.clone(),
+        labels: Vec::new(),
+        // secure values in fixtures without a key are assumed to use the default one
+        kms_key_id: parameter.kms_key_id.clone()
+            .or_else(|| (parameter.r#type == "SecureString").then(|| "alias/aws/ssm".to_string())),
+        description: parameter.description.clone(),
+    }
+}
+
+/// Mimic the service: secure values are returned only when decryption is requested
+fn hide_secure(param: Parameter, decrypt: bool) -> Parameter {
+    if param.r#type == "SecureString" && !decrypt {
+        Parameter { value: "<encrypted>".to_string(), ..param }
+    } else {
+        param
+    }
+}
@@ -0,0 +1,66 @@
+use crate::actions::{CommandInvocation, CommandInvocationList, ProviderActions};
+use crate::app::AppError;
+use crate::cli::{CLI, RunArgs};
+use crate::outputs;
+use futures::future::try_join_all;
+use log::{info, debug};
+
+use std::time::{Duration, Instant};
+
+/// Delay between subsequent invocation status checks
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Resolve target instances, run shell command on them and render per-instance results
+pub async fn execute_run<P: ProviderActions>(provider: &P, args: &RunArgs, cli: &CLI) -> Result<(), AppError> {
+    let (targets, query) = args.selection();
+
+    debug!("Resolving instances to run command on");
+    let instances = provider.resolve_instances(&targets, &query).await?;
+    if instances.instances.is_empty() {
+        return Err(AppError::NotFound("No instances match given targets".to_string()));
+    }
+    let instance_ids: Vec<String> = instances.instances.iter()
+        .map(|instance| instance.instance_id.clone())
+        .collect();
+
+    let command = args.command.join(" ");
+    info!("Running '{}' on {} instance(s)...", command, instance_ids.len());
+    let sent = provider.send_command(&instance_ids, &command, args.timeout).await?;
+
+    // agent enforces execution timeout itself, allow some slack for delivery
+    let timeout = Duration::from_secs(args.timeout) + Duration::from_secs(60);
+    let polls = sent.iter().map(|invocation| wait_for_invocation(provider, invocation, timeout));
+    let mut invocations = try_join_all(polls).await?;
+
+    for invocation in invocations.iter_mut() {
+        invocation.name = instances.instances.iter()
+            .find(|instance| instance.instance_id == invocation.instance_id)
+            .map(|instance| instance.name.clone())
+            .filter(|name| !name.is_empty());
+    }
+
+    let failed = invocations.iter().filter(|invocation| !invocation.is_success()).count();
+    let total = invocations.len();
+    outputs::render(CommandInvocationList { invocations }, &cli.output())?;
+
+    if failed > 0 {
+        return Err(AppError::GeneralError(format!("Command failed on {} of {} instance(s)", failed, total)));
+    }
+    Ok(())
+}
+
+/// Poll single invocation until it reaches its final status
+async fn wait_for_invocation<P: ProviderActions>(provider: &P, sent: &CommandInvocation, timeout: Duration) -> Result<CommandInvocation, AppError> {
+    let started = Instant::now();
+    loop {
+        let invocation = provider.get_command_invocation(&sent.command_id, &sent.instance_id).await?;
+        if invocation.is_finished() {
+            debug!("Command {} on {} finished with status {}", sent.command_id, sent.instance_id, invocation.status);
+            return Ok(invocation);
+        }
+        if started.elapsed() >= timeout {
+            return Err(AppError::TimeoutError);
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
@@ -0,0 +1,138 @@
+pub mod client;
+pub mod protocol;
+pub mod server;
+
+use crate::actions::{ProviderActions, Session, SessionRequest};
+use crate::app::AppError;
+use crate::cli::SessionClient;
+use log::{info, debug, warn};
+use serde_json::json;
+use tokio::process::Command;
+
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Executable of the official Session Manager client
+const PLUGIN: &str = "session-manager-plugin";
+
+#[derive(Debug)]
+pub enum SessionError {
+    ConnectionError(String),
+    ProtocolError(String),
+    IOError(io::Error),
+    /// Requested feature cannot be handled by the native client
+    Unsupported(String),
+    /// Session manager plugin exited with non-zero code
+    PluginFailed(Option<i32>),
+}
+
+impl fmt::Display for SessionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SessionError::ConnectionError(msg) => write!(f, "Session connection failed: {}", msg),
+            SessionError::ProtocolError(msg) => write!(f, "Session protocol error: {}", msg),
+            SessionError::IOError(err) => write!(f, "IO Error: {}", err),
+            SessionError::Unsupported(msg) => write!(f, "{}", msg),
+            SessionError::PluginFailed(Some(code)) => write!(f, "{} exited with code {}", PLUGIN, code),
+            SessionError::PluginFailed(None) => write!(f, "{} was terminated by a signal", PLUGIN),
+        }
+    }
+}
+
+impl std::error::Error for SessionError {}
+
+impl From<io::Error> for SessionError {
+    fn from(err: io::Error) -> Self {
+        SessionError::IOError(err)
+    }
+}
+
+impl From<serde_json::Error> for SessionError {
+    fn from(err: serde_json::Error) -> Self {
+        SessionError::ProtocolError(err.to_string())
+    }
+}
+
+impl From<tokio_tungstenite::tungstenite::Error> for SessionError {
+    fn from(err: tokio_tungstenite::tungstenite::Error) -> Self {
+        SessionError::ConnectionError(err.to_string())
+    }
+}
+
+/// Start a session and attach the terminal to it, using selected client
+pub async fn execute_session<P: ProviderActions>(provider: &P, request: &SessionRequest, client: &SessionClient) -> Result<(), AppError> {
+    let session = provider.start_session(request).await?;
+    debug!("Started session {}", session.session_id);
+
+    match select_plugin(client, &session)? {
+        Some(plugin) => {
+            // plugin terminates the session by itself once it exits
+            run_plugin(&plugin, &session, request).await?;
+        }
+        None => {
+            let result = client::run(&session).await;
+            info!("Terminating session {}...", session.session_id);
+            if let Err(e) = provider.terminate_session(&session.session_id).await {
+                warn!("Failed to terminate session {}: {}", session.session_id, e);
+            }
+            result?;
+        }
+    }
+    Ok(())
+}
+
+/// Session manager plugin that should drive given session, `None` for native client
+pub fn select_plugin(client: &SessionClient, session: &Session) -> Result<Option<PathBuf>, AppError> {
+    match client {
+        SessionClient::Native => Ok(None),
+        SessionClient::Plugin => find_plugin()
+            .map(Some)
+            .ok_or_else(|| AppError::NotFound(format!("{} was not found in PATH", PLUGIN))),
+        // plugin talks only to real, TLS protected endpoints
+        SessionClient::Auto => Ok(find_plugin().filter(|_| session.stream_url.starts_with("wss://"))),
+    }
+}
+
+/// Look for session manager plugin in directories listed in PATH
+pub fn find_plugin() -> Option<PathBuf> {
+    let name = if cfg!(windows) { format!("{}.exe", PLUGIN) } else { PLUGIN.to_string() };
+    let paths = std::env::var_os("PATH")?;
+    std::env::split_paths(&paths)
+        .map(|dir| dir.join(&name))
+        .find(|path| path.is_file())
+}
+
+/// Hand the session over to session manager plugin, the same way AWS CLI does
+pub async fn run_plugin(plugin: &Path, session: &Session, request: &SessionRequest) -> Result<(), SessionError> {
+    info!("Handing session {} over to {}...", session.session_id, plugin.display());
+    let response = json!({
+        "SessionId": session.session_id,
+        "TokenValue": session.token_value,
+        "StreamUrl": session.stream_url,
+    });
+
+    let mut parameters = json!({"Target": request.target});
+    if let Some(document) = &request.document_name {
+        parameters["DocumentName"] = json!(document);
+    }
+    if !request.parameters.is_empty() {
+        parameters["Parameters"] = json!(request.parameters);
+    }
+
+    let region = session.region.clone().unwrap_or_default();
+    let status = Command::new(plugin)
+        .arg(response.to_string())
+        .arg(&region)
+        .arg("StartSession")
+        .arg(session.profile.clone().unwrap_or_default())
+        .arg(parameters.to_string())
+        .arg(format!("https://ssm.{}.amazonaws.com", region))
+        .status()
+        .await?;
+
+    if !status.success() {
+        return Err(SessionError::PluginFailed(status.code()));
+    }
+    Ok(())
+}
@@ -0,0 +1,385 @@
+use crate::actions::Session;
+use crate::session::SessionError;
+use crate::session::protocol::{ClientMessage, PayloadType, OpenDataChannelInput};
+use crate::session::protocol::{ChannelClosed, HandshakeRequest, HandshakeResponse, ProcessedClientAction, SizeData};
+use crate::session::protocol::{ACKNOWLEDGE, CHANNEL_CLOSED, CLIENT_VERSION, INPUT_STREAM_DATA, OUTPUT_STREAM_DATA};
+use futures::{SinkExt, StreamExt};
+use log::{info, debug, warn};
+use tokio::io::{AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+use tokio_tungstenite::connect_async;
+use tokio_tungstenite::tungstenite::Message;
+
+use std::collections::BTreeMap;
+use std::io::{IsTerminal, Read};
+use std::time::Duration;
+
+/// Interval of keep-alive pings sent over the connection
+const PING_INTERVAL: Duration = Duration::from_secs(300);
+
+/// Interval of terminal size checks
+const RESIZE_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Byte sent by the terminal on Ctrl-D
+pub const END_OF_TRANSMISSION: u8 = 0x04;
+
+/// Size of data chunks read from local input
+const CHUNK_SIZE: usize = 1024;
+
+/// Flag sent once local end of forwarded connection closes
+const DISCONNECT_TO_PORT: u32 = 1;
+
+/// Flag sent by the agent when remote port cannot be reached
+const CONNECT_TO_PORT_ERROR: u32 = 3;
+
+/// Client version announced for port sessions, old enough for the agent to
+/// forward a single connection without multiplexing
+const PORT_CLIENT_VERSION: &str = "1.0.0.0";
+
+/// Kind of stream carried by the session
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SessionKind {
+    /// Interactive shell attached to the terminal
+    Shell,
+    /// Single forwarded TCP connection
+    Port,
+}
+
+impl SessionKind {
+    /// Session type the agent is expected to request during handshake
+    fn session_type(&self) -> &'static str {
+        match self {
+            SessionKind::Shell => "Standard_Stream",
+            SessionKind::Port => "Port",
+        }
+    }
+
+    fn client_version(&self) -> &'static str {
+        match self {
+            SessionKind::Shell => CLIENT_VERSION,
+            SessionKind::Port => PORT_CLIENT_VERSION,
+        }
+    }
+}
+
+/// Outcome of processing a message received from the agent
+#[derive(Debug)]
+pub enum Event {
+    /// Message that has to be sent back to the agent
+    Send(ClientMessage),
+    /// Data to be written to the terminal or forwarded connection
+    Output(Vec<u8>),
+    /// Handshake completed, input can be sent from now on
+    Ready,
+    /// Agent closed the channel, with optional farewell message
+    Closed(String),
+}
+
+/// State of the data channel: sequence numbers, reordering and handshake
+#[derive(Debug)]
+pub struct DataChannel {
+    kind: SessionKind,
+    /// Sequence number of the next input message
+    out_sequence: i64,
+    /// Sequence number of the next expected output message
+    in_sequence: i64,
+    /// Output messages received ahead of order
+    pending: BTreeMap<i64, ClientMessage>,
+    /// Whether handshake has completed
+    pub ready: bool,
+}
+
+impl DataChannel {
+    pub fn new(kind: SessionKind) -> Self {
+        DataChannel {
+            kind,
+            out_sequence: 0,
+            in_sequence: 0,
+            pending: BTreeMap::new(),
+            ready: false,
+        }
+    }
+
+    /// Wrap data into the next input message
+    pub fn input(&mut self, payload_type: PayloadType, payload: Vec<u8>) -> ClientMessage {
+        let message = ClientMessage::new(INPUT_STREAM_DATA, self.out_sequence, 0, payload_type, payload);
+        self.out_sequence += 1;
+        message
+    }
+
+    /// Input message announcing terminal dimensions
+    pub fn resize(&mut self, cols: u16, rows: u16) -> Result<ClientMessage, SessionError> {
+        let size = serde_json::to_vec(&SizeData { cols: cols.into(), rows: rows.into() })?;
+        Ok(self.input(PayloadType::Size, size))
+    }
+
+    /// Input message telling the agent that forwarded connection was closed
+    pub fn disconnect(&mut self) -> ClientMessage {
+        self.input(PayloadType::Flag, DISCONNECT_TO_PORT.to_be_bytes().to_vec())
+    }
+
+    /// Process message received from the agent
+    pub fn receive(&mut self, message: ClientMessage) -> Result<Vec<Event>, SessionError> {
+        match message.message_type.as_str() {
+            OUTPUT_STREAM_DATA => {
+                let mut events = vec![Event::Send(ClientMessage::acknowledge(&message)?)];
+                if message.sequence_number < self.in_sequence {
+                    debug!("Ignoring duplicate message {}", message.sequence_number);
+                    return Ok(events);
+                }
+
+                self.pending.insert(message.sequence_number, message);
+                while let Some(message) = self.pending.remove(&self.in_sequence) {
+                    self.in_sequence += 1;
+                    events.extend(self.process(message)?);
+                }
+                Ok(events)
+            }
+            ACKNOWLEDGE => {
+                debug!("Received acknowledgement");
+                Ok(Vec::new())
+            }
+            CHANNEL_CLOSED => {
+                let closed: ChannelClosed = serde_json::from_slice(&message.payload)?;
+                info!("Session {} closed by the agent", closed.session_id);
+                Ok(vec![Event::Closed(closed.output)])
+            }
+            other => {
+                debug!("Ignoring {} message", other);
+                Ok(Vec::new())
+            }
+        }
+    }
+
+    /// Handle payload of an in-order output message
+    fn process(&mut self, message: ClientMessage) -> Result<Vec<Event>, SessionError> {
+        match message.payload_type {
+            PayloadType::Output | PayloadType::StdErr => Ok(vec![Event::Output(message.payload)]),
+            PayloadType::HandshakeRequest => {
+                let request: HandshakeRequest = serde_json::from_slice(&message.payload)?;
+                debug!("Handshake requested by agent {}", request.agent_version);
+                let response = self.handshake_response(&request)?;
+                Ok(vec![Event::Send(self.input(PayloadType::HandshakeResponse, response))])
+            }
+            PayloadType::HandshakeComplete => {
+                debug!("Handshake completed");
+                self.ready = true;
+                Ok(vec![Event::Ready])
+            }
+            PayloadType::Flag if message.payload == CONNECT_TO_PORT_ERROR.to_be_bytes() => {
+                Err(SessionError::ConnectionError("Remote port cannot be reached from the instance".to_string()))
+            }
+            other => {
+                debug!("Ignoring output payload of type {:?}", other);
+                Ok(Vec::new())
+            }
+        }
+    }
+
+    /// Accept plain stream sessions, reject anything requiring encryption
+    fn handshake_response(&self, request: &HandshakeRequest) -> Result<Vec<u8>, SessionError> {
+        let mut processed = Vec::new();
+        for action in &request.requested_client_actions {
+            match action.action_type.as_str() {
+                "SessionType" => {
+                    let session_type = action.action_parameters["SessionType"].as_str().unwrap_or_default();
+                    if session_type != self.kind.session_type() {
+                        return Err(SessionError::Unsupported(format!("Session type {} is not supported natively", session_type)));
+                    }
+                    processed.push(ProcessedClientAction {
+                        action_type: action.action_type.clone(),
+                        action_status: 1,
+                        action_result: serde_json::Value::Null,
+                        error: String::new(),
+                    });
+                }
+                "KMSEncryption" => {
+                    return Err(SessionError::Unsupported("KMS encrypted sessions are not supported natively".to_string()));
+                }
+                other => {
+                    warn!("Unsupported handshake action {}", other);
+                    processed.push(ProcessedClientAction {
+                        action_type: other.to_string(),
+                        action_status: 3,
+                        action_result: serde_json::Value::Null,
+                        error: format!("{} is not supported", other),
+                    });
+                }
+            }
+        }
+
+        let response = HandshakeResponse {
+            client_version: self.kind.client_version().to_string(),
+            processed_client_actions: processed,
+            errors: Vec::new(),
+        };
+        Ok(serde_json::to_vec(&response)?)
+    }
+}
+
+/// Terminal switched to raw mode for the lifetime of the guard
+struct RawMode {
+    enabled: bool,
+}
+
+impl RawMode {
+    fn enable() -> Result<Self, SessionError> {
+        let enabled = std::io::stdin().is_terminal();
+        if enabled {
+            crossterm::terminal::enable_raw_mode()?;
+        }
+        Ok(RawMode { enabled })
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        if self.enabled {
+            let _ = crossterm::terminal::disable_raw_mode();
+        }
+    }
+}
+
+/// Read stdin on a dedicated thread, so that pending read does not block shutdown
+fn spawn_stdin_reader() -> mpsc::Receiver<Vec<u8>> {
+    let (sender, receiver) = mpsc::channel(16);
+    std::thread::spawn(move || {
+        let mut stdin = std::io::stdin();
+        let mut buffer = [0u8; CHUNK_SIZE];
+        loop {
+            match stdin.read(&mut buffer) {
+                Ok(0) | Err(_) => break,
+                Ok(count) => {
+                    if sender.blocking_send(buffer[..count].to_vec()).is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    });
+    receiver
+}
+
+/// Read forwarded connection on a separate task
+fn spawn_connection_reader(mut reader: tokio::net::tcp::OwnedReadHalf) -> (mpsc::Receiver<Vec<u8>>, tokio::task::JoinHandle<()>) {
+    let (sender, receiver) = mpsc::channel(16);
+    let handle = tokio::spawn(async move {
+        let mut buffer = [0u8; CHUNK_SIZE];
+        loop {
+            match reader.read(&mut buffer).await {
+                Ok(0) | Err(_) => break,
+                Ok(count) => {
+                    if sender.send(buffer[..count].to_vec()).await.is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    });
+    (receiver, handle)
+}
+
+/// Connect to session stream and attach current terminal to it until the channel closes
+pub async fn run(session: &Session) -> Result<(), SessionError> {
+    let raw_mode = RawMode::enable()?;
+    let result = drive(session, SessionKind::Shell, spawn_stdin_reader(), tokio::io::stdout()).await;
+    drop(raw_mode);
+
+    if let Ok(Some(farewell)) = &result {
+        eprintln!("\n{}", farewell);
+    }
+    result.map(|_| ())
+}
+
+/// Connect to port session stream and forward single local connection through it
+pub async fn forward(session: &Session, connection: TcpStream) -> Result<(), SessionError> {
+    let (reader, writer) = connection.into_split();
+    let (input, reading) = spawn_connection_reader(reader);
+    let result = drive(session, SessionKind::Port, input, writer).await;
+    reading.abort();
+    result.map(|_| ())
+}
+
+/// Exchange data between local input/output and session stream until either side closes
+///
+/// Returns farewell message of the agent, if the channel was closed by it.
+async fn drive<W>(session: &Session, kind: SessionKind, mut input: mpsc::Receiver<Vec<u8>>, mut output: W) -> Result<Option<String>, SessionError>
+where
+    W: AsyncWrite + Unpin,
+{
+    info!("Connecting to session {}...", session.session_id);
+    let (socket, _) = connect_async(session.stream_url.as_str()).await?;
+    let (mut sink, mut stream) = socket.split();
+
+    let open = serde_json::to_string(&OpenDataChannelInput::new(&session.token_value, kind.client_version()))?;
+    sink.send(Message::Text(open)).await?;
+
+    let mut channel = DataChannel::new(kind);
+    let mut ping = tokio::time::interval(PING_INTERVAL);
+    let mut resize = tokio::time::interval(RESIZE_INTERVAL);
+    let mut size = None;
+    let mut input_open = true;
+
+    loop {
+        tokio::select! {
+            data = input.recv(), if channel.ready && input_open => {
+                let message = match (data, kind) {
+                    (Some(data), _) => channel.input(PayloadType::Output, data),
+                    (None, SessionKind::Shell) => {
+                        // end of piped input behaves like Ctrl-D pressed in the terminal
+                        debug!("Input closed, waiting for the session to end");
+                        input_open = false;
+                        channel.input(PayloadType::Output, vec![END_OF_TRANSMISSION])
+                    }
+                    (None, SessionKind::Port) => {
+                        debug!("Local connection closed");
+                        sink.send(Message::Binary(channel.disconnect().encode())).await?;
+                        return Ok(None);
+                    }
+                };
+                sink.send(Message::Binary(message.encode())).await?;
+            }
+
+            message = stream.next() => {
+                let data = match message {
+                    Some(Ok(Message::Binary(data))) => data,
+                    Some(Ok(Message::Close(_))) | None => {
+                        debug!("Connection closed");
+                        break;
+                    }
+                    Some(Ok(_)) => continue,
+                    Some(Err(e)) => return Err(e.into()),
+                };
+
+                for event in channel.receive(ClientMessage::decode(&data)?)? {
+                    match event {
+                        Event::Send(message) => sink.send(Message::Binary(message.encode())).await?,
+                        Event::Output(data) => {
+                            output.write_all(&data).await?;
+                            output.flush().await?;
+                        }
+                        // force sending terminal size with the next check
+                        Event::Ready => size = None,
+                        Event::Closed(farewell) => {
+                            return Ok(Some(farewell).filter(|f| !f.is_empty()));
+                        }
+                    }
+                }
+            }
+
+            _ = resize.tick(), if channel.ready && kind == SessionKind::Shell => {
+                let current = crossterm::terminal::size().ok();
+                if let Some((cols, rows)) = current.filter(|_| current != size) {
+                    debug!("Terminal size changed to {}x{}", cols, rows);
+                    let message = channel.resize(cols, rows)?;
+                    sink.send(Message::Binary(message.encode())).await?;
+                    size = current;
+                }
+            }
+
+            _ = ping.tick() => sink.send(Message::Ping(Vec::new())).await?,
+        }
+    }
+    Ok(None)
+}
@@ -0,0 +1,288 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::session::SessionError;
+
+// Layout of binary Session Manager message header, as used by session-manager-plugin
+const HEADER_LENGTH: u32 = 116;
+const MESSAGE_TYPE_OFFSET: usize = 4;
+const MESSAGE_TYPE_LENGTH: usize = 32;
+const SCHEMA_VERSION_OFFSET: usize = 36;
+const CREATED_DATE_OFFSET: usize = 40;
+const SEQUENCE_NUMBER_OFFSET: usize = 48;
+const FLAGS_OFFSET: usize = 56;
+const MESSAGE_ID_OFFSET: usize = 64;
+const PAYLOAD_DIGEST_OFFSET: usize = 80;
+const PAYLOAD_TYPE_OFFSET: usize = 112;
+const PAYLOAD_LENGTH_OFFSET: usize = 116;
+const PAYLOAD_OFFSET: usize = 120;
+
+pub const INPUT_STREAM_DATA: &str = "input_stream_data";
+pub const OUTPUT_STREAM_DATA: &str = "output_stream_data";
+pub const ACKNOWLEDGE: &str = "acknowledge";
+pub const CHANNEL_CLOSED: &str = "channel_closed";
+
+/// Version reported to the agent for interactive sessions
+pub const CLIENT_VERSION: &str = "1.2.0.0";
+
+/// Kind of data carried by stream messages
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PayloadType {
+    Undefined = 0,
+    Output = 1,
+    Error = 2,
+    Size = 3,
+    Parameter = 4,
+    HandshakeRequest = 5,
+    HandshakeResponse = 6,
+    HandshakeComplete = 7,
+    EncChallengeRequest = 8,
+    EncChallengeResponse = 9,
+    Flag = 10,
+    StdErr = 11,
+    ExitCode = 12,
+}
+
+impl From<u32> for PayloadType {
+    fn from(value: u32) -> Self {
+        match value {
+            1 => PayloadType::Output,
+            2 => PayloadType::Error,
+            3 => PayloadType::Size,
+            4 => PayloadType::Parameter,
+            5 => PayloadType::HandshakeRequest,
+            6 => PayloadType::HandshakeResponse,
+            7 => PayloadType::HandshakeComplete,
+            8 => PayloadType::EncChallengeRequest,
+            9 => PayloadType::EncChallengeResponse,
+            10 => PayloadType::Flag,
+            11 => PayloadType::StdErr,
+            12 => PayloadType::ExitCode,
+            _ => PayloadType::Undefined,
+        }
+    }
+}
+
+/// Single binary message exchanged over the data channel
+#[derive(Debug, Clone)]
+pub struct ClientMessage {
+    pub message_type: String,
+    pub schema_version: u32,
+    pub created_date: u64,
+    pub sequence_number: i64,
+    pub flags: u64,
+    pub message_id: Uuid,
+    pub payload_type: PayloadType,
+    pub payload: Vec<u8>,
+}
+
+impl ClientMessage {
+    pub fn new(message_type: &str, sequence_number: i64, flags: u64, payload_type: PayloadType, payload: Vec<u8>) -> Self {
+        ClientMessage {
+            message_type: message_type.to_string(),
+            schema_version: 1,
+            created_date: now_millis(),
+            sequence_number,
+            flags,
+            message_id: Uuid::new_v4(),
+            payload_type,
+            payload,
+        }
+    }
+
+    /// Acknowledgement of a received stream message
+    pub fn acknowledge(message: &ClientMessage) -> Result<Self, SessionError> {
+        let content = AcknowledgeContent {
+            acknowledged_message_type: message.message_type.clone(),
+            acknowledged_message_id: message.message_id.to_string(),
+            acknowledged_message_sequence_number: message.sequence_number,
+            is_sequential_message: true,
+        };
+        let payload = serde_json::to_vec(&content)
+            .map_err(|e| SessionError::ProtocolError(e.to_string()))?;
+        Ok(ClientMessage::new(ACKNOWLEDGE, 0, 3, PayloadType::Undefined, payload))
+    }
+
+    /// Serialize message into wire format
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buffer = vec![0u8; PAYLOAD_OFFSET + self.payload.len()];
+
+        buffer[0..4].copy_from_slice(&HEADER_LENGTH.to_be_bytes());
+
+        // message type is padded with spaces
+        let message_type = &mut buffer[MESSAGE_TYPE_OFFSET..MESSAGE_TYPE_OFFSET + MESSAGE_TYPE_LENGTH];
+        message_type.fill(b' ');
+        let type_bytes = self.message_type.as_bytes();
+        let type_length = type_bytes.len().min(MESSAGE_TYPE_LENGTH);
+        message_type[..type_length].copy_from_slice(&type_bytes[..type_length]);
+
+        buffer[SCHEMA_VERSION_OFFSET..SCHEMA_VERSION_OFFSET + 4].copy_from_slice(&self.schema_version.to_be_bytes());
+        buffer[CREATED_DATE_OFFSET..CREATED_DATE_OFFSET + 8].copy_from_slice(&self.created_date.to_be_bytes());
+        buffer[SEQUENCE_NUMBER_OFFSET..SEQUENCE_NUMBER_OFFSET + 8].copy_from_slice(&self.sequence_number.to_be_bytes());
+        buffer[FLAGS_OFFSET..FLAGS_OFFSET + 8].copy_from_slice(&self.flags.to_be_bytes());
+
+        // message ID is stored with its less significant half first
+        let id = self.message_id.as_bytes();
+        buffer[MESSAGE_ID_OFFSET..MESSAGE_ID_OFFSET + 8].copy_from_slice(&id[8..16]);
+        buffer[MESSAGE_ID_OFFSET + 8..MESSAGE_ID_OFFSET + 16].copy_from_slice(&id[0..8]);
+
+        let digest = Sha256::digest(&self.payload);
+        buffer[PAYLOAD_DIGEST_OFFSET..PAYLOAD_DIGEST_OFFSET + 32].copy_from_slice(&digest);
+
+        buffer[PAYLOAD_TYPE_OFFSET..PAYLOAD_TYPE_OFFSET + 4].copy_from_slice(&(self.payload_type as u32).to_be_bytes());
+        buffer[PAYLOAD_LENGTH_OFFSET..PAYLOAD_LENGTH_OFFSET + 4].copy_from_slice(&(self.payload.len() as u32).to_be_bytes());
+        buffer[PAYLOAD_OFFSET..].copy_from_slice(&self.payload);
+
+        buffer
+    }
+
+    /// Parse message from wire format, validating lengths and payload digest
+    pub fn decode(data: &[u8]) -> Result<Self, SessionError> {
+        if data.len() < PAYLOAD_OFFSET {
+            return Err(SessionError::ProtocolError(format!("Message too short: {} bytes", data.len())));
+        }
+
+        let message_type = String::from_utf8_lossy(&data[MESSAGE_TYPE_OFFSET..MESSAGE_TYPE_OFFSET + MESSAGE_TYPE_LENGTH])
+            .trim_matches(|c| c == ' ' || c == '\0')
+            .to_string();
+
+        let mut id = [0u8; 16];
+        id[8..16].copy_from_slice(&data[MESSAGE_ID_OFFSET..MESSAGE_ID_OFFSET + 8]);
+        id[0..8].copy_from_slice(&data[MESSAGE_ID_OFFSET + 8..MESSAGE_ID_OFFSET + 16]);
+
+        let header_length = read_u32(data, 0) as usize;
+        if data.len() < header_length + 4 {
+            return Err(SessionError::ProtocolError(format!("Invalid header length: {}", header_length)));
+        }
+        let payload_length = read_u32(data, header_length) as usize;
+        let payload_start = header_length + 4;
+        if data.len() < payload_start + payload_length {
+            return Err(SessionError::ProtocolError("Payload length exceeds message size".to_string()));
+        }
+        let payload = data[payload_start..payload_start + payload_length].to_vec();
+
+        if payload_length > 0 && Sha256::digest(&payload).as_slice() != &data[PAYLOAD_DIGEST_OFFSET..PAYLOAD_DIGEST_OFFSET + 32] {
+            return Err(SessionError::ProtocolError("Payload digest mismatch".to_string()));
+        }
+
+        Ok(ClientMessage {
+            message_type,
+            schema_version: read_u32(data, SCHEMA_VERSION_OFFSET),
+            created_date: read_u64(data, CREATED_DATE_OFFSET),
+            sequence_number: read_u64(data, SEQUENCE_NUMBER_OFFSET) as i64,
+            flags: read_u64(data, FLAGS_OFFSET),
+            message_id: Uuid::from_bytes(id),
+            payload_type: PayloadType::from(read_u32(data, PAYLOAD_TYPE_OFFSET)),
+            payload,
+        })
+    }
+}
+
+/// First text frame sent after connecting, authenticating the data channel
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct OpenDataChannelInput {
+    pub message_schema_version: String,
+    pub request_id: String,
+    pub token_value: String,
+    pub client_id: String,
+    pub client_version: String,
+}
+
+impl OpenDataChannelInput {
+    pub fn new(token_value: &str, client_version: &str) -> Self {
+        OpenDataChannelInput {
+            message_schema_version: "1.0".to_string(),
+            request_id: Uuid::new_v4().to_string(),
+            token_value: token_value.to_string(),
+            client_id: Uuid::new_v4().to_string(),
+            client_version: client_version.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct AcknowledgeContent {
+    pub acknowledged_message_type: String,
+    pub acknowledged_message_id: String,
+    pub acknowledged_message_sequence_number: i64,
+    pub is_sequential_message: bool,
+}
+
+/// Action requested by the agent during handshake
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct RequestedClientAction {
+    pub action_type: String,
+    #[serde(default)]
+    pub action_parameters: serde_json::Value,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct HandshakeRequest {
+    #[serde(default)]
+    pub agent_version: String,
+    #[serde(default)]
+    pub requested_client_actions: Vec<RequestedClientAction>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ProcessedClientAction {
+    pub action_type: String,
+    /// 1 - success, 2 - failed, 3 - unsupported
+    pub action_status: u32,
+    #[serde(default)]
+    pub action_result: serde_json::Value,
+    #[serde(default)]
+    pub error: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct HandshakeResponse {
+    pub client_version: String,
+    pub processed_client_actions: Vec<ProcessedClientAction>,
+    pub errors: Vec<String>,
+}
+
+/// Terminal dimensions sent with `Size` payload
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SizeData {
+    pub cols: u32,
+    pub rows: u32,
+}
+
+/// Payload of `channel_closed` message
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ChannelClosed {
+    #[serde(default)]
+    pub session_id: String,
+    #[serde(default)]
+    pub output: String,
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    let mut bytes = [0u8; 4];
+    bytes.copy_from_slice(&data[offset..offset + 4]);
+    u32::from_be_bytes(bytes)
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&data[offset..offset + 8]);
+    u64::from_be_bytes(bytes)
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
@@ -0,0 +1,110 @@
+use crate::session::SessionError;
+use crate::session::client::END_OF_TRANSMISSION;
+use crate::session::protocol::{ClientMessage, PayloadType, ChannelClosed, HandshakeRequest};
+use crate::session::protocol::{CHANNEL_CLOSED, INPUT_STREAM_DATA, OUTPUT_STREAM_DATA};
+use futures::{SinkExt, StreamExt};
+use log::{info, debug, warn};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_tungstenite::accept_async;
+use tokio_tungstenite::tungstenite::Message;
+
+/// Serve stand-in sessions on given listener until the process is stopped
+///
+/// Mimics Session Manager endpoint closely enough for the native client: every connection
+/// performs a handshake and then echoes its input back, until Ctrl-D (or end of
+/// client's input) closes the channel. Port sessions are echoed the same way.
+/// Used together with the mock provider, whose `session.json` fixture points to it.
+pub async fn serve(listener: TcpListener) -> Result<(), SessionError> {
+    info!("Serving stand-in sessions on {}", listener.local_addr()?);
+    loop {
+        let (stream, address) = listener.accept().await?;
+        debug!("Accepted session connection from {}", address);
+        tokio::spawn(async move {
+            if let Err(e) = handle(stream).await {
+                warn!("Session from {} failed: {}", address, e);
+            }
+        });
+    }
+}
+
+/// Drive a single session connection
+async fn handle(stream: TcpStream) -> Result<(), SessionError> {
+    let socket = accept_async(stream).await?;
+    let (mut sink, mut stream) = socket.split();
+
+    // first frame opens the data channel with session token
+    match stream.next().await {
+        Some(Ok(Message::Text(open))) => {
+            let open: serde_json::Value = serde_json::from_str(&open)?;
+            debug!("Opened data channel with token {}", open["TokenValue"]);
+        }
+        _ => return Err(SessionError::ProtocolError("Expected data channel open frame".to_string())),
+    }
+
+    let mut sequence = 0;
+    let mut output = |payload_type: PayloadType, payload: Vec<u8>| {
+        let message = ClientMessage::new(OUTPUT_STREAM_DATA, sequence, 0, payload_type, payload);
+        sequence += 1;
+        Message::Binary(message.encode())
+    };
+
+    // session type is not announced, so that both shell and port clients accept the handshake
+    let request = HandshakeRequest {
+        agent_version: "ctk-stand-in".to_string(),
+        requested_client_actions: Vec::new(),
+    };
+    sink.send(output(PayloadType::HandshakeRequest, serde_json::to_vec(&request)?)).await?;
+
+    while let Some(frame) = stream.next().await {
+        let data = match frame? {
+            Message::Binary(data) => data,
+            Message::Close(_) => break,
+            _ => continue,
+        };
+
+        let message = ClientMessage::decode(&data)?;
+        if message.message_type != INPUT_STREAM_DATA {
+            continue;
+        }
+        sink.send(Message::Binary(ClientMessage::acknowledge(&message)?.encode())).await?;
+
+        match message.payload_type {
+            PayloadType::HandshakeResponse => {
+                sink.send(output(PayloadType::HandshakeComplete, b"{}".to_vec())).await?;
+                sink.send(output(PayloadType::Output, b"Connected to ctk stand-in session, Ctrl-D to exit\r\n".to_vec())).await?;
+            }
+            PayloadType::Size => debug!("Terminal resized: {}", String::from_utf8_lossy(&message.payload)),
+            PayloadType::Flag => {
+                debug!("Forwarded connection closed by the client");
+                break;
+            }
+            PayloadType::Output => {
+                let (input, closing) = match message.payload.iter().position(|byte| *byte == END_OF_TRANSMISSION) {
+                    Some(end) => (&message.payload[..end], true),
+                    None => (&message.payload[..], false),
+                };
+
+                // terminal in raw mode sends bare carriage returns
+                let echo: Vec<u8> = input.iter()
+                    .flat_map(|byte| if *byte == b'\r' { vec![b'\r', b'\n'] } else { vec![*byte] })
+                    .collect();
+                if !echo.is_empty() {
+                    sink.send(output(PayloadType::Output, echo)).await?;
+                }
+                if !closing {
+                    continue;
+                }
+
+                let closed = ChannelClosed {
+                    session_id: "stand-in".to_string(),
+                    output: "Session closed".to_string(),
+                };
+                let message = ClientMessage::new(CHANNEL_CLOSED, 0, 0, PayloadType::Undefined, serde_json::to_vec(&closed)?);
+                sink.send(Message::Binary(message.encode())).await?;
+                break;
+            }
+            other => debug!("Ignoring input payload of type {:?}", other),
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,330 @@
+use crate::actions::{ProviderActions, ProviderError, Session, SessionRequest};
+use crate::app::AppError;
+use crate::cli::SessionClient;
+use crate::outputs::Renderable;
+use crate::session::{SessionError, client, find_plugin, run_plugin, select_plugin};
+use futures::StreamExt;
+use futures::future::try_join_all;
+use futures::stream::FuturesUnordered;
+use log::{info, debug, warn};
+use serde::{Deserialize, Serialize};
+use tokio::net::{TcpListener, TcpStream};
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Document forwarding a port of the instance itself
+const PORT_DOCUMENT: &str = "AWS-StartPortForwardingSession";
+
+/// Document forwarding a port of a host reachable from the instance
+const REMOTE_HOST_DOCUMENT: &str = "AWS-StartPortForwardingSessionToRemoteHost";
+
+/// Delay before the first reconnection attempt, doubled with every failed one
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+/// Upper bound of delay between reconnection attempts
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(60);
+
+/// Tunnel given on the command line or defined in `[tunnels.<name>]` configuration section
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TunnelSpec {
+    /// Instance ID or unique name the traffic goes through
+    pub target: String,
+    /// Local port to listen on, defaults to remote port
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub local_port: Option<u16>,
+    /// Host reachable from the instance, the instance itself when missing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remote_host: Option<String>,
+    pub remote_port: u16,
+}
+
+impl TunnelSpec {
+    fn local_port(&self) -> u16 {
+        self.local_port.unwrap_or(self.remote_port)
+    }
+
+    /// Session request forwarding ports of this tunnel through given instance
+    fn request(&self, instance_id: &str) -> SessionRequest {
+        let mut parameters = BTreeMap::new();
+        parameters.insert("portNumber".to_string(), vec![self.remote_port.to_string()]);
+        parameters.insert("localPortNumber".to_string(), vec![self.local_port().to_string()]);
+
+        let document = match &self.remote_host {
+            Some(host) => {
+                parameters.insert("host".to_string(), vec![host.clone()]);
+                REMOTE_HOST_DOCUMENT
+            }
+            None => PORT_DOCUMENT,
+        };
+
+        SessionRequest {
+            target: instance_id.to_string(),
+            document_name: Some(document.to_string()),
+            parameters,
+        }
+    }
+}
+
+/// Tunnel opened by a running ctk process
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ActiveTunnel {
+    pub name: String,
+    pub instance_id: String,
+    pub local_port: u16,
+    pub remote_host: Option<String>,
+    pub remote_port: u16,
+    /// Client forwarding the traffic, `native` or `plugin`
+    pub client: String,
+    pub pid: u32,
+    /// Start time, in seconds since UNIX epoch
+    pub started: u64,
+}
+
+impl ActiveTunnel {
+    /// Time elapsed since the tunnel was opened, e.g. `2h 05m`
+    pub fn uptime(&self) -> String {
+        let elapsed = now_secs().saturating_sub(self.started);
+        match elapsed {
+            0..60 => format!("{}s", elapsed),
+            60..3600 => format!("{}m {:02}s", elapsed / 60, elapsed % 60),
+            _ => format!("{}h {:02}m", elapsed / 3600, elapsed % 3600 / 60),
+        }
+    }
+}
+
+/// Tunnels opened by running ctk processes
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TunnelList {
+    pub tunnels: Vec<ActiveTunnel>,
+}
+
+impl Renderable for TunnelList {
+    type Record = ActiveTunnel;
+
+    fn records(&self) -> &[ActiveTunnel] {
+        &self.tunnels
+    }
+}
+
+/// State file announcing a running tunnel, removed once the tunnel is closed
+struct Registration {
+    path: PathBuf,
+}
+
+impl Registration {
+    fn create(tunnel: &ActiveTunnel) -> io::Result<Self> {
+        let dir = state_dir();
+        fs::create_dir_all(&dir)?;
+
+        let path = dir.join(format!("{}-{}.json", tunnel.pid, tunnel.local_port));
+        debug!("Registering tunnel {} in {}", tunnel.name, path.display());
+        fs::write(&path, serde_json::to_string(tunnel)?)?;
+        Ok(Registration { path })
+    }
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Directory with state files of running tunnels (`$XDG_STATE_HOME/ctk/tunnels` or `~/.local/state/ctk/tunnels`)
+pub fn state_dir() -> PathBuf {
+    let base = std::env::var_os("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("state")))
+        .unwrap_or(PathBuf::from("."));
+    base.join("ctk").join("tunnels")
+}
+
+/// Read tunnels registered by running processes, cleaning up after processes which are gone
+pub fn list_active() -> io::Result<TunnelList> {
+    let dir = state_dir();
+    let mut tunnels = Vec::new();
+    if !dir.is_dir() {
+        return Ok(TunnelList { tunnels });
+    }
+
+    for entry in fs::read_dir(&dir)? {
+        let path = entry?.path();
+        if path.extension().is_none_or(|ext| ext != "json") {
+            continue;
+        }
+
+        let tunnel: Option<ActiveTunnel> = fs::read_to_string(&path).ok()
+            .and_then(|content| serde_json::from_str(&content).ok());
+        match tunnel {
+            Some(tunnel) if is_running(tunnel.pid) => tunnels.push(tunnel),
+            Some(_) => {
+                debug!("Removing stale tunnel state {}", path.display());
+                let _ = fs::remove_file(&path);
+            }
+            None => warn!("Skipping invalid tunnel state {}", path.display()),
+        }
+    }
+
+    tunnels.sort_by(|a, b| a.name.cmp(&b.name).then(a.local_port.cmp(&b.local_port)));
+    Ok(TunnelList { tunnels })
+}
+
+/// Check whether process is still alive, assumed on platforms without procfs
+fn is_running(pid: u32) -> bool {
+    if cfg!(target_os = "linux") {
+        Path::new("/proc").join(pid.to_string()).exists()
+    } else {
+        true
+    }
+}
+
+/// Open given tunnels concurrently and keep them up until interrupted
+pub async fn execute_tunnels<P: ProviderActions>(provider: &P, tunnels: &[(String, TunnelSpec)], client: &SessionClient) -> Result<(), AppError> {
+    let runs = tunnels.iter().map(|(name, spec)| run_tunnel(provider, name, spec, client));
+
+    tokio::select! {
+        result = try_join_all(runs) => result.map(|_| ()),
+        _ = tokio::signal::ctrl_c() => {
+            info!("Closing tunnels...");
+            Ok(())
+        }
+    }
+}
+
+/// Resolve tunnel target, register the tunnel and forward traffic with selected client
+async fn run_tunnel<P: ProviderActions>(provider: &P, name: &str, spec: &TunnelSpec, client: &SessionClient) -> Result<(), AppError> {
+    let instance = provider.resolve_instance(&spec.target).await?;
+    let request = spec.request(&instance.instance_id);
+
+    // whether plugin can drive the sessions is known only once a session exists
+    let mut first_session = None;
+    let plugin = match client {
+        SessionClient::Native => None,
+        SessionClient::Auto if find_plugin().is_none() => None,
+        _ => {
+            let session = provider.start_session(&request).await?;
+            let plugin = select_plugin(client, &session)?;
+            if plugin.is_some() {
+                first_session = Some(session);
+            } else if let Err(e) = provider.terminate_session(&session.session_id).await {
+                warn!("Failed to terminate session {}: {}", session.session_id, e);
+            }
+            plugin
+        }
+    };
+
+    let _registration = Registration::create(&ActiveTunnel {
+        name: name.to_string(),
+        instance_id: instance.instance_id.clone(),
+        local_port: spec.local_port(),
+        remote_host: spec.remote_host.clone(),
+        remote_port: spec.remote_port,
+        client: if plugin.is_some() { "plugin" } else { "native" }.to_string(),
+        pid: std::process::id(),
+        started: now_secs(),
+    }).map_err(SessionError::from)?;
+
+    info!(
+        "Tunnel {}: localhost:{} -> {}:{} through {}",
+        name, spec.local_port(), spec.remote_host.as_deref().unwrap_or("localhost"), spec.remote_port, instance.instance_id,
+    );
+    match plugin {
+        Some(plugin) => run_with_plugin(provider, name, &request, &plugin, first_session).await,
+        None => run_native(provider, name, &request, spec.local_port()).await,
+    }
+}
+
+/// Keep plugin running, starting a new session whenever the previous one ends
+async fn run_with_plugin<P: ProviderActions>(provider: &P, name: &str, request: &SessionRequest, plugin: &Path, mut session: Option<Session>) -> Result<(), AppError> {
+    let mut delay = RECONNECT_DELAY;
+    loop {
+        let started = Instant::now();
+        let result = match session.take() {
+            Some(session) => Ok(session),
+            None => provider.start_session(request).await,
+        };
+
+        match result {
+            Ok(session) => match run_plugin(plugin, &session, request).await {
+                Ok(_) => warn!("Tunnel {} closed", name),
+                Err(e) => warn!("Tunnel {} dropped: {}", name, e),
+            },
+            Err(e) if is_transient(&e) => warn!("Cannot start session for tunnel {}: {}", name, e),
+            Err(e) => return Err(e.into()),
+        }
+
+        // tunnel which stayed up for a while is considered healthy again
+        if started.elapsed() > MAX_RECONNECT_DELAY {
+            delay = RECONNECT_DELAY;
+        }
+        info!("Reconnecting tunnel {} in {}s...", name, delay.as_secs());
+        tokio::time::sleep(delay).await;
+        delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+    }
+}
+
+/// Listen on local port, forwarding every accepted connection through its own session
+async fn run_native<P: ProviderActions>(provider: &P, name: &str, request: &SessionRequest, local_port: u16) -> Result<(), AppError> {
+    let listener = TcpListener::bind(("127.0.0.1", local_port)).await.map_err(|e| {
+        AppError::GeneralError(format!("Cannot listen on port {} for tunnel {}: {}", local_port, name, e))
+    })?;
+
+    let mut connections = FuturesUnordered::new();
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => {
+                let (connection, address) = accepted.map_err(SessionError::from)?;
+                debug!("Tunnel {} accepted connection from {}", name, address);
+                connections.push(forward_connection(provider, name, request, connection));
+            }
+            Some(_) = connections.next(), if !connections.is_empty() => {}
+        }
+    }
+}
+
+/// Forward single connection, retrying session start on transient failures
+async fn forward_connection<P: ProviderActions>(provider: &P, name: &str, request: &SessionRequest, connection: TcpStream) {
+    let mut delay = RECONNECT_DELAY;
+    let session = loop {
+        match provider.start_session(request).await {
+            Ok(session) => break session,
+            Err(e) if is_transient(&e) && delay < MAX_RECONNECT_DELAY => {
+                warn!("Cannot start session for tunnel {}, retrying in {}s: {}", name, delay.as_secs(), e);
+                tokio::time::sleep(delay).await;
+                delay *= 2;
+            }
+            Err(e) => {
+                warn!("Cannot start session for tunnel {}: {}", name, e);
+                return;
+            }
+        }
+    };
+
+    if let Err(e) = client::forward(&session, connection).await {
+        warn!("Connection through tunnel {} failed: {}", name, e);
+    }
+    if let Err(e) = provider.terminate_session(&session.session_id).await {
+        debug!("Failed to terminate session {}: {}", session.session_id, e);
+    }
+}
+
+/// Check whether failure may go away by itself, so that retrying makes sense
+fn is_transient(error: &ProviderError) -> bool {
+    matches!(error,
+        ProviderError::ConnectionError
+        | ProviderError::TimeoutError
+        | ProviderError::Throttled {..}
+        | ProviderError::EndpointError(_)
+        | ProviderError::ServiceError {..}
+    )
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}